@@ -0,0 +1,385 @@
+// ADAPTIVE SORTING
+// smart_sort looks at a small sample of the input before picking an algorithm.
+//
+// Heuristics (checked in this order):
+// 1. Nearly sorted: at least 90% of adjacent sample pairs are already in order.
+//    An adaptive merge sort handles this in close to O(n).
+// 2. Few distinct values: at most a quarter of the sampled values are distinct.
+//    Bucketing by value handles this in O(n log d) for d distinct values.
+// 3. Anything else goes to the plain merge sort.
+
+use crate::sequential::{merge, merge_sort};
+
+// How many evenly spaced elements analyze looks at
+const SAMPLE_LEN: usize = 64;
+const NEARLY_SORTED: f64 = 0.9;
+const FEW_DISTINCT: f64 = 0.25;
+// Inputs this short are sorted with insertion sort by the adaptive merge sort
+pub(crate) const INSERTION_LEN: usize = 16;
+// The counting path gives up and falls back to merge sort past this many distinct values
+const MAX_DISTINCT: usize = 1024;
+// explain recommends parallel sorting from this many elements on; below it, starting the
+// threads costs about as much as they save
+const PARALLEL_LEN: usize = 100_000;
+
+/// Which algorithm `smart_sort` runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Merge sort that skips merges of halves that are already in order, with insertion sort for short ranges.
+    AdaptiveMerge,
+    /// Groups elements into one bucket per distinct value, then concatenates the buckets in order.
+    Counting,
+    /// The regular `merge_sort`.
+    Merge,
+}
+
+/// Estimates taken from a sample of the input by `analyze`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputProfile {
+    /// Length of the whole input.
+    pub len: usize,
+    /// How many elements were sampled.
+    pub sample_len: usize,
+    /// `sortedness` of the sample.
+    pub sortedness: f64,
+    /// Distinct values in the sample divided by the sample length (1.0 for an empty input).
+    pub distinct_ratio: f64,
+}
+
+impl InputProfile {
+    /// The algorithm the heuristics pick for this profile.
+    pub fn recommended(&self) -> Algorithm {
+        if self.sortedness >= NEARLY_SORTED {
+            Algorithm::AdaptiveMerge
+        } else if self.distinct_ratio <= FEW_DISTINCT {
+            Algorithm::Counting
+        } else {
+            Algorithm::Merge
+        }
+    }
+}
+
+/// What `sort_with_outcome` had to do. Both variants hold the sorted data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortOutcome<T> {
+    /// The input was already in order and is returned untouched.
+    AlreadySorted(Vec<T>),
+    /// At least one element had to move.
+    Sorted(Vec<T>),
+}
+
+impl<T> SortOutcome<T> {
+    /// The sorted data, whichever variant this is.
+    pub fn into_inner(self) -> Vec<T> {
+        match self {
+            SortOutcome::AlreadySorted(data) | SortOutcome::Sorted(data) => data,
+        }
+    }
+}
+
+/// Samples up to 64 evenly spaced elements of `data` to estimate how sorted it is and how
+/// many distinct values it has. The cost doesn't depend on the input length.
+pub fn analyze<T: PartialOrd>(data: &[T]) -> InputProfile {
+    // Evenly spaced positions keep the sample representative of the whole input
+    let step = data.len().div_ceil(SAMPLE_LEN).max(1);
+    let sample: Vec<&T> = data.iter().step_by(step).collect();
+
+    let sortedness = sortedness(&sample);
+
+    // Sorting the (tiny) sample brings equal values next to each other so they can be counted
+    let distinct_ratio = if sample.is_empty() {
+        1.0
+    } else {
+        let sorted = merge_sort(sample.clone());
+        let distinct = 1 + sorted.windows(2).filter(|pair| pair[0] < pair[1]).count();
+        distinct as f64 / sample.len() as f64
+    };
+
+    InputProfile { len: data.len(), sample_len: sample.len(), sortedness, distinct_ratio }
+}
+
+/// Describes in plain English what `analyze` sees in `data`: how sorted it looks, how many
+/// distinct values it seems to have, which algorithm `smart_sort` would pick and why, and whether
+/// the input is big enough for parallel sorting to pay off. One line per point.
+///
+/// Everything except the length comes from the sample, so it's an estimate, not a guarantee.
+pub fn explain<T: PartialOrd>(data: &[T]) -> String {
+    let profile = analyze(data);
+    let mut lines = vec![format!("{} elements, {} sampled.", profile.len, profile.sample_len)];
+
+    lines.push(if profile.sortedness == 1.0 {
+        "Sortedness: every sampled pair is in order, so the input looks already sorted.".to_string()
+    } else {
+        format!("Sortedness: {:.0}% of sampled adjacent pairs are in order.", profile.sortedness * 100.0)
+    });
+    lines.push(format!(
+        "Distinct values: about {:.0}% of the sampled elements are distinct.",
+        profile.distinct_ratio * 100.0
+    ));
+
+    let algorithm = profile.recommended();
+    let reason = match algorithm {
+        Algorithm::AdaptiveMerge => "nearly sorted input lets it skip most merges, close to O(n)",
+        Algorithm::Counting => "few distinct values fit in a handful of buckets, O(n log d)",
+        Algorithm::Merge => "nothing to exploit, so a plain O(n log n) merge sort",
+    };
+    lines.push(format!("Algorithm: {algorithm:?} ({reason})."));
+
+    lines.push(if profile.len >= PARALLEL_LEN {
+        format!("Threads: parallelism recommended ({} elements is enough to split up).", profile.len)
+    } else {
+        format!("Threads: parallelism not worth it below {PARALLEL_LEN} elements.")
+    });
+
+    lines.join("\n")
+}
+
+/// Measures how sorted `data` already is, without sorting it.
+///
+/// The metric is the fraction of adjacent pairs `(data[i], data[i + 1])` with `data[i] <= data[i + 1]`:
+/// 1.0 means fully sorted (including inputs of fewer than 2 elements), and strictly descending
+/// input gives 0.0. Random data lands around 0.5. It takes one O(n) pass.
+pub fn sortedness<T: PartialOrd>(data: &[T]) -> f64 {
+    let pairs = data.len().saturating_sub(1);
+    if pairs == 0 {
+        return 1.0;
+    }
+
+    let in_order = data.windows(2).filter(|pair| pair[0] <= pair[1]).count();
+    in_order as f64 / pairs as f64
+}
+
+/// Returns the length of the longest ascending (non-decreasing) prefix of `data`, i.e. how many
+/// elements from the front are already in sorted order. Empty input gives 0.
+pub fn sorted_prefix_len<T: PartialOrd>(data: &[T]) -> usize {
+    if data.is_empty() {
+        return 0;
+    }
+
+    data.windows(2).take_while(|pair| pair[0] <= pair[1]).count() + 1
+}
+
+/// Sorts `data` (ascending) where new elements have been appended to an already sorted `Vec`.
+///
+/// Only the unsorted tail after `sorted_prefix_len(&data)` is sorted; it is then merged with the
+/// prefix, so appending m elements to n sorted ones costs O(m log m + n) instead of a full sort.
+/// Any input sorts correctly, it's just no faster than `merge_sort` when the prefix is short.
+pub fn sort_appended<T: PartialOrd + Copy>(data: Vec<T>) -> Vec<T> {
+    let prefix = sorted_prefix_len(&data);
+    sort_after_prefix(data, prefix)
+}
+
+// Sorts everything after the first `prefix` (already sorted) elements and merges it in
+fn sort_after_prefix<T: PartialOrd + Copy>(mut data: Vec<T>, prefix: usize) -> Vec<T> {
+    if prefix == data.len() {
+        return data;
+    }
+
+    let tail = merge_sort(data.split_off(prefix));
+    merge(data, tail)
+}
+
+/// Sorts `data` (ascending) and reports whether anything had to move, so callers can skip
+/// invalidating caches or re-rendering when the input was already in order.
+///
+/// The check is the `sorted_prefix_len` scan. Like `sort_appended`, an unsorted input then only
+/// has the part after its sorted prefix sorted, before the two are merged.
+pub fn sort_with_outcome<T: PartialOrd + Copy>(data: Vec<T>) -> SortOutcome<T> {
+    let prefix = sorted_prefix_len(&data);
+    if prefix == data.len() {
+        SortOutcome::AlreadySorted(data)
+    } else {
+        SortOutcome::Sorted(sort_after_prefix(data, prefix))
+    }
+}
+
+/// Sorts `data` (ascending) with whichever algorithm `analyze` recommends for it.
+pub fn smart_sort<T: PartialOrd + Copy>(data: Vec<T>) -> Vec<T> {
+    match analyze(&data).recommended() {
+        Algorithm::AdaptiveMerge => adaptive_merge_sort(data),
+        Algorithm::Counting => counting_sort_by_value(data),
+        Algorithm::Merge => merge_sort(data),
+    }
+}
+
+fn adaptive_merge_sort<T: PartialOrd + Copy>(mut data: Vec<T>) -> Vec<T> {
+    if data.len() <= INSERTION_LEN {
+        insertion_sort(&mut data);
+        return data;
+    }
+
+    let middle = data.len()/2;
+    let mut left = adaptive_merge_sort(data[..middle].to_vec());
+    let right = adaptive_merge_sort(data[middle..].to_vec());
+
+    // Halves that are already in order only need to be joined, which is what makes sorted input cheap
+    if left[left.len() - 1] <= right[0] {
+        left.extend_from_slice(&right);
+        left
+    } else {
+        merge(left, right)
+    }
+}
+
+pub(crate) fn insertion_sort<T: PartialOrd + Copy>(data: &mut [T]) {
+    for i in 1..data.len() {
+        let value = data[i];
+        // Shift larger elements right until the gap reaches value's position
+        let mut j = i;
+        while j > 0 && data[j - 1] > value {
+            data[j] = data[j - 1];
+            j -= 1;
+        }
+        data[j] = value;
+    }
+}
+
+fn counting_sort_by_value<T: PartialOrd + Copy>(data: Vec<T>) -> Vec<T> {
+    // keys[i] is the value shared by everything in buckets[i], kept in ascending order
+    let mut keys: Vec<T> = Vec::new();
+    let mut buckets: Vec<Vec<T>> = Vec::new();
+
+    for &value in &data {
+        // keys[pos] is the first key not below value, so it's value's bucket if it isn't above it either
+        let pos = keys.partition_point(|key| *key < value);
+        if keys.get(pos).is_some_and(|key| *key <= value) {
+            buckets[pos].push(value);
+        } else {
+            // The sample was misleading, so don't let the bucket list grow without bound
+            if keys.len() == MAX_DISTINCT {
+                return merge_sort(data);
+            }
+            keys.insert(pos, value);
+            buckets.insert(pos, vec![value]);
+        }
+    }
+
+    // Buckets are pushed to in input order, so the result is stable
+    buckets.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{keyed, random_values, stable_sorted, std_sorted};
+
+    #[test]
+    fn analyze_picks_each_branch_for_representative_inputs() {
+        let sorted: Vec<i64> = (0..10_000).collect();
+        let mut nearly_sorted = sorted.clone();
+        nearly_sorted.swap(10, 11);
+        let few_distinct = random_values(10_000, 4, 1);
+        let random = random_values(10_000, 1_000_000, 2);
+
+        assert_eq!(analyze(&sorted).recommended(), Algorithm::AdaptiveMerge);
+        assert_eq!(analyze(&nearly_sorted).recommended(), Algorithm::AdaptiveMerge);
+        assert_eq!(analyze(&few_distinct).recommended(), Algorithm::Counting);
+        assert_eq!(analyze(&random).recommended(), Algorithm::Merge);
+    }
+
+    #[test]
+    fn analyze_samples_at_most_sample_len_elements() {
+        let profile = analyze(&random_values(100_000, 10, 3));
+        assert_eq!(profile.len, 100_000);
+        assert!(profile.sample_len <= SAMPLE_LEN);
+        assert_eq!(analyze::<i64>(&[]).sample_len, 0);
+    }
+
+    #[test]
+    fn smart_sort_sorts_whatever_branch_it_takes() {
+        let inputs = [
+            (0..5000).collect(),
+            random_values(5000, 3, 4),
+            random_values(5000, 1_000_000, 5),
+            Vec::new(),
+        ];
+        for data in inputs {
+            assert_eq!(smart_sort(data.clone()), std_sorted(&data));
+        }
+    }
+
+    #[test]
+    fn counting_branch_falls_back_when_the_sample_misleads() {
+        // 6400 elements are sampled every 100th, and only those positions repeat a few values:
+        // the sample says few distinct, but the input has far more than MAX_DISTINCT
+        let data: Vec<i64> = (0..6400).map(|i| if i % 100 == 0 { i % 3 } else { 1_000_000 - i }).collect();
+        assert_eq!(analyze(&data).recommended(), Algorithm::Counting);
+        assert_eq!(smart_sort(data.clone()), std_sorted(&data));
+    }
+
+    #[test]
+    fn smart_sort_is_stable_on_every_branch() {
+        for values in [random_values(3000, 4, 7), random_values(3000, 100_000, 8)] {
+            assert_eq!(smart_sort(keyed(&values)), stable_sorted(&values));
+        }
+    }
+
+    #[test]
+    fn sort_with_outcome_reports_whether_anything_moved() {
+        assert_eq!(sort_with_outcome(vec![1, 2, 2, 5]), SortOutcome::AlreadySorted(vec![1, 2, 2, 5]));
+        assert_eq!(sort_with_outcome(Vec::<i32>::new()), SortOutcome::AlreadySorted(vec![]));
+        assert_eq!(sort_with_outcome(vec![1, 3, 2]), SortOutcome::Sorted(vec![1, 2, 3]));
+
+        for len in 2..100 {
+            let data = random_values(len, 1000, len as u64);
+            let expected = std_sorted(&data);
+            let outcome = sort_with_outcome(data.clone());
+            assert_eq!(matches!(outcome, SortOutcome::AlreadySorted(_)), data == expected);
+            assert_eq!(outcome.into_inner(), expected);
+        }
+    }
+
+    #[test]
+    fn sortedness_measures_adjacent_pairs_in_order() {
+        assert_eq!(sortedness(&[1, 2, 3, 4]), 1.0);
+        assert_eq!(sortedness(&[4, 3, 2, 1]), 0.0);
+        assert_eq!(sortedness(&[1, 3, 2]), 0.5);
+        assert_eq!(sortedness::<i32>(&[]), 1.0);
+    }
+
+    #[test]
+    fn sorted_prefix_len_counts_the_ascending_front() {
+        assert_eq!(sorted_prefix_len(&[1, 2, 2, 5, 3, 9]), 4);
+        assert_eq!(sorted_prefix_len(&[1, 2, 3]), 3);
+        assert_eq!(sorted_prefix_len(&[3, 1]), 1);
+        assert_eq!(sorted_prefix_len(&[0.5]), 1);
+        assert_eq!(sorted_prefix_len::<i32>(&[]), 0);
+    }
+
+    #[test]
+    fn sort_appended_merges_the_new_tail_in() {
+        for (prefix_len, tail_len) in [(0, 0), (0, 50), (50, 0), (1000, 1), (1000, 300), (3, 1000)] {
+            let mut values = random_values(prefix_len, 100, 5);
+            values.sort();
+            values.extend(random_values(tail_len, 100, 6));
+            // Ties between the sorted prefix and the appended tail keep the prefix first
+            assert_eq!(
+                sort_appended(keyed(&values)),
+                stable_sorted(&values),
+                "prefix {prefix_len}, tail {tail_len}"
+            );
+        }
+    }
+
+    #[test]
+    fn explain_describes_the_input_line_by_line() {
+        let sorted: Vec<i64> = (0..PARALLEL_LEN as i64).collect();
+        let report = explain(&sorted);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 5, "{report}");
+        assert_eq!(lines[0], format!("{PARALLEL_LEN} elements, {SAMPLE_LEN} sampled."));
+        assert!(lines[1].contains("looks already sorted"), "{report}");
+        assert!(lines[3].starts_with("Algorithm: AdaptiveMerge"), "{report}");
+        assert!(lines[4].contains("parallelism recommended"), "{report}");
+
+        let report = explain(&random_values(1000, 1_000_000, 3));
+        assert!(report.contains("Algorithm: Merge ("), "{report}");
+        assert!(report.contains("not worth it below"), "{report}");
+
+        let report = explain(&random_values(PARALLEL_LEN, 1_000_000, 8));
+        assert!(report.contains("Algorithm: Merge ("), "{report}");
+        assert!(report.contains("parallelism recommended"), "{report}");
+        assert!(explain(&random_values(1000, 3, 4)).contains("Algorithm: Counting"));
+        assert!(explain::<i32>(&[]).starts_with("0 elements, 0 sampled."));
+    }
+}
@@ -0,0 +1,55 @@
+// LOCALE-AWARE STRING SORTING (feature "collation")
+// Byte-wise string comparison puts "Zebra" before "apple" and "é" after "z". A Unicode collator
+// compares the way a reader of the given language expects instead.
+
+use icu_collator::options::CollatorOptions;
+use icu_collator::Collator;
+use icu_locale_core::Locale;
+
+use crate::sequential::merge_sort_by;
+
+/// Sorts strings with the Unicode collation rules of `locale` (a BCP 47 tag like `"en"`, `"de"`
+/// or `"sv"`), so accented letters sort next to their base letters where that language says so.
+///
+/// The sort is stable. A tag that can't be parsed falls back to the root collation order,
+/// which is the language-neutral Unicode default.
+pub fn merge_sort_collated(data: Vec<String>, locale: &str) -> Vec<String> {
+    let locale = Locale::try_from_str(locale).unwrap_or(Locale::UNKNOWN);
+    // Locales without their own tailoring fall back to a parent locale or the root, so this can't fail
+    let collator = Collator::try_new(locale.into(), CollatorOptions::default())
+        .expect("compiled collation data covers every locale");
+
+    merge_sort_by(data, |a, b| collator.compare(a, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn accented_letters_sort_next_to_their_base_letter() {
+        // Byte order would put "Zebra" first and both "é" words last
+        let words = strings(&["zebra", "éclair", "Zebra", "apple", "eclair", "Eclair"]);
+        let sorted = merge_sort_collated(words, "en");
+        assert_eq!(sorted, ["apple", "eclair", "Eclair", "éclair", "zebra", "Zebra"]);
+    }
+
+    #[test]
+    fn the_locale_decides_where_accented_letters_go() {
+        let words = strings(&["zebra", "öl", "ol"]);
+        // English treats "ö" as an accented "o", Swedish as a letter of its own after "z"
+        assert_eq!(merge_sort_collated(words.clone(), "en"), ["ol", "öl", "zebra"]);
+        assert_eq!(merge_sort_collated(words, "sv"), ["ol", "zebra", "öl"]);
+    }
+
+    #[test]
+    fn an_invalid_tag_falls_back_to_the_root_order() {
+        // Byte order would be "Zoo", "apple", "egg", "éclair"
+        let sorted = merge_sort_collated(strings(&["Zoo", "éclair", "apple", "egg"]), "not a locale!");
+        assert_eq!(sorted, ["apple", "éclair", "egg", "Zoo"]);
+    }
+}
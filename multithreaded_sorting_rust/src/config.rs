@@ -0,0 +1,184 @@
+// SORT CONFIGURATION
+// A builder for the options that apply on top of whichever algorithm ends up running.
+
+use std::num::NonZeroUsize;
+
+use crate::adaptive::smart_sort;
+use crate::parallel::{balanced_ranges, chunk_ranges, sort_ranges_parallel};
+use crate::unstable::quick_sort;
+
+/// How a parallel sort splits its input between threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Balancing {
+    /// Every thread gets a chunk of the same length.
+    #[default]
+    Even,
+    /// Chunk lengths are sized by how disordered each region looks, so that sorted regions end up
+    /// in bigger chunks and every thread gets about as much work. The estimate costs one extra
+    /// pass over the input.
+    Adaptive,
+}
+
+/// Options for sorting, set with builder methods and applied by `sort`.
+/// The defaults behave like `smart_sort`.
+#[derive(Debug, Clone, Copy)]
+pub struct SortConfig {
+    stable: bool,
+    break_ties_by_index: bool,
+    threads: NonZeroUsize,
+    balancing: Balancing,
+    pin_threads: bool,
+}
+
+impl Default for SortConfig {
+    fn default() -> Self {
+        Self {
+            stable: true,
+            break_ties_by_index: false,
+            threads: NonZeroUsize::MIN,
+            balancing: Balancing::Even,
+            pin_threads: false,
+        }
+    }
+}
+
+impl SortConfig {
+    /// A config with the default options: stable, single-threaded, without index tie breaking.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether equal elements must keep their input order (the default), like `slice::sort`.
+    ///
+    /// Turning it off is the `slice::sort_unstable` equivalent: the sort is free to use
+    /// `quick_sort`, which works in place without scratch buffers and is usually faster, but may
+    /// reorder equal elements. Combined with `break_ties_by_index` the output is still fully deterministic.
+    pub fn stable(mut self, stable: bool) -> Self {
+        self.stable = stable;
+        self
+    }
+
+    /// When enabled, each element is paired with its original index and the pairs are sorted by
+    /// `(value, index)`. Equal values then always come out in input order, whatever algorithm runs,
+    /// so the output is byte-for-byte reproducible.
+    ///
+    /// This costs one extra `usize` per element (plus any padding in `(T, usize)`) for the
+    /// duration of the sort, and a final pass to strip the indices again.
+    pub fn break_ties_by_index(mut self, enabled: bool) -> Self {
+        self.break_ties_by_index = enabled;
+        self
+    }
+
+    /// How many threads to sort with (1 by default). With more than one, each thread sorts a
+    /// chunk of the input and the sorted chunks are merged at the end.
+    pub fn threads(mut self, threads: NonZeroUsize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// How the input is split into chunks when sorting with more than one thread.
+    pub fn balancing(mut self, balancing: Balancing) -> Self {
+        self.balancing = balancing;
+        self
+    }
+
+    /// When enabled (and the crate is built with the `affinity` feature), each worker thread of a
+    /// multi-threaded sort is pinned to its own core, so the OS can't migrate it away from its
+    /// cached data halfway through.
+    ///
+    /// This is only a hint: it silently does nothing without the feature, on platforms that don't
+    /// support setting affinity, or when sorting with a single thread.
+    pub fn pin_threads(mut self, enabled: bool) -> Self {
+        self.pin_threads = enabled;
+        self
+    }
+
+    /// Sorts `data` (ascending) using this config.
+    pub fn sort<T: PartialOrd + Copy + Send + Sync>(&self, data: Vec<T>) -> Vec<T> {
+        if !self.break_ties_by_index {
+            return self.run(data);
+        }
+
+        // Indices are unique, so no two pairs ever compare equal and the order is total
+        let indexed: Vec<(T, usize)> = data.into_iter().enumerate().map(|(i, value)| (value, i)).collect();
+        self.run(indexed).into_iter().map(|(value, _)| value).collect()
+    }
+
+    // Runs the sort, once any tie breaking has been set up
+    fn run<T: PartialOrd + Copy + Send + Sync>(&self, mut data: Vec<T>) -> Vec<T> {
+        if self.threads.get() == 1 || data.len() <= 1 {
+            return self.run_sequential(data);
+        }
+
+        let ranges = match self.balancing {
+            Balancing::Even => chunk_ranges(data.len(), self.threads),
+            Balancing::Adaptive => balanced_ranges(&data, self.threads),
+        };
+        sort_ranges_parallel(&mut data, &ranges, self.pin_threads, |chunk| self.run_sequential(chunk));
+        data
+    }
+
+    // Picks the algorithm for a single thread's share of the work
+    fn run_sequential<T: PartialOrd + Copy>(&self, data: Vec<T>) -> Vec<T> {
+        if self.stable {
+            smart_sort(data)
+        } else {
+            quick_sort(data)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{keyed, random_values, stable_sorted, std_sorted};
+
+    fn threads(n: usize) -> NonZeroUsize {
+        NonZeroUsize::new(n).unwrap()
+    }
+
+    #[test]
+    fn break_ties_by_index_keeps_equal_values_in_input_order() {
+        let values = random_values(5000, 20, 5);
+        for n in [1, 4] {
+            // Even the unstable algorithm can't reorder ties once they carry their index
+            let config = SortConfig::new().stable(false).break_ties_by_index(true).threads(threads(n));
+            assert_eq!(config.sort(keyed(&values)), stable_sorted(&values), "{n} threads");
+        }
+    }
+
+    #[test]
+    fn stable_toggle_picks_a_sort_that_still_sorts() {
+        let values = random_values(3000, 40, 9);
+        // Stable is the default, so ties keep their input order without index tie breaking
+        assert_eq!(SortConfig::new().sort(keyed(&values)), stable_sorted(&values));
+        for n in [1, 3] {
+            let unstable = SortConfig::new().stable(false).threads(threads(n));
+            assert_eq!(unstable.sort(values.clone()), std_sorted(&values), "{n} threads");
+        }
+    }
+
+    #[test]
+    fn adaptive_balancing_sorts_like_even_balancing() {
+        let mut values: Vec<i64> = (0..20_000).collect();
+        values.extend(random_values(20_000, 50, 13));
+        for n in [2, 4, 7] {
+            for balancing in [Balancing::Even, Balancing::Adaptive] {
+                let config = SortConfig::new().threads(threads(n)).balancing(balancing);
+                // Still stable: ties from the random half keep their input order
+                assert_eq!(config.sort(keyed(&values)), stable_sorted(&values), "{n} threads, {balancing:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn pin_threads_does_not_change_the_result() {
+        // With the affinity feature this really pins, including more workers than cores
+        let values = random_values(10_000, 1000, 21);
+        let cores = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        for n in [1, 2, cores + 3] {
+            let config = SortConfig::new().threads(threads(n)).pin_threads(true);
+            assert_eq!(config.sort(values.clone()), std_sorted(&values), "{n} threads");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// ERRORS
+
+use std::error::Error;
+use std::fmt;
+
+/// Errors returned by the sorts that validate their input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortError {
+    /// `data[index]` is larger than the `max_value` a counting sort was given.
+    ValueOutOfRange { index: usize, value: u32, max_value: u32 },
+    /// A counting sort's `max_value + 1` counts don't fit in a `usize` (only possible on 32-bit
+    /// targets such as wasm32, with `max_value == u32::MAX`).
+    RangeTooLarge { max_value: u32 },
+    /// The input couldn't be parsed as JSON. Holds the parser's message.
+    InvalidJson(String),
+    /// The JSON parsed fine but isn't an array.
+    NotAnArray,
+    /// Element `index` of the JSON array isn't a number.
+    NotANumber { index: usize },
+    /// Chunk `index` was passed in as sorted, but isn't.
+    UnsortedChunk { index: usize },
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortError::ValueOutOfRange { index, value, max_value } => {
+                write!(f, "value {value} at index {index} is larger than the maximum of {max_value}")
+            }
+            SortError::RangeTooLarge { max_value } => {
+                write!(f, "a count array for values up to {max_value} is too large for this target")
+            }
+            SortError::InvalidJson(message) => write!(f, "invalid JSON: {message}"),
+            SortError::NotAnArray => write!(f, "expected a JSON array"),
+            SortError::NotANumber { index } => write!(f, "element {index} of the JSON array is not a number"),
+            SortError::UnsortedChunk { index } => write!(f, "chunk {index} is not sorted"),
+        }
+    }
+}
+
+impl Error for SortError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_describe_themselves() {
+        let out_of_range = SortError::ValueOutOfRange { index: 2, value: 9, max_value: 5 };
+        assert_eq!(out_of_range.to_string(), "value 9 at index 2 is larger than the maximum of 5");
+        assert_eq!(SortError::UnsortedChunk { index: 1 }.to_string(), "chunk 1 is not sorted");
+        assert_eq!(SortError::NotANumber { index: 0 }.to_string(), "element 0 of the JSON array is not a number");
+    }
+}
@@ -0,0 +1,154 @@
+// GROUPING FUNCTIONS
+// Data-engineering helpers that use the sort to bring equal values together.
+
+use std::collections::BTreeMap;
+
+use crate::sequential::{merge_sort, merge_sort_by_key, merge_sort_owned};
+
+/// Replaces every value with a small integer code.
+///
+/// Returns one code per input element plus the sorted vocabulary of distinct values, so
+/// `vocab[codes[i] as usize] == data[i]` for every `i`. All n values are cloned and sorted once,
+/// equal neighbours are dropped to form the vocabulary, and each element is then looked up in it
+/// with a binary search, so it takes O(n log n) time and O(n) extra memory.
+///
+/// # Panics
+/// Panics if there are more than `u32::MAX` distinct values.
+pub fn factorize<T: Ord + Clone>(data: &[T]) -> (Vec<u32>, Vec<T>) {
+    let mut vocab = merge_sort_owned(data.to_vec());
+    vocab.dedup();
+
+    let codes = data
+        .iter()
+        .map(|value| {
+            let index = vocab.binary_search(value).expect("every value is in the vocabulary");
+            u32::try_from(index).expect("more than u32::MAX distinct values")
+        })
+        .collect();
+
+    (codes, vocab)
+}
+
+/// Partitions `data` into groups by `key`.
+///
+/// Iterating the returned map visits the groups in ascending key order, and each group keeps
+/// its elements in input order.
+pub fn sort_grouped<T: Copy, K: Ord + Copy, F: Fn(&T) -> K>(data: Vec<T>, key: F) -> BTreeMap<K, Vec<T>> {
+    let mut groups: BTreeMap<K, Vec<T>> = BTreeMap::new();
+    // The map keeps its keys ordered, so only the elements need to be appended in input order
+    for value in data {
+        groups.entry(key(&value)).or_default().push(value);
+    }
+    groups
+}
+
+/// Sorts `data` by `key` and folds every run of equal keys into one value, like a
+/// `GROUP BY key` with an aggregate.
+///
+/// Each group starts from a clone of `init` and `reduce` is applied to its elements in input
+/// order. The groups come back in ascending key order. Sorting is a stable merge sort by key,
+/// followed by a single pass over the sorted data.
+pub fn sort_and_reduce<T, K, V, KF, RF>(data: Vec<T>, key: KF, init: V, mut reduce: RF) -> Vec<(K, V)>
+where
+    K: PartialOrd,
+    V: Clone,
+    KF: Fn(&T) -> K,
+    RF: FnMut(V, T) -> V,
+{
+    let sorted = merge_sort_by_key(data, &key);
+
+    let mut groups: Vec<(K, V)> = Vec::new();
+    for value in sorted {
+        let value_key = key(&value);
+        // Keep folding into the last group while the key stays the same, otherwise start a new one
+        let (group_key, acc) = match groups.pop() {
+            Some((last_key, acc)) if last_key == value_key => (last_key, acc),
+            last => {
+                groups.extend(last);
+                (value_key, init.clone())
+            }
+        };
+        groups.push((group_key, reduce(acc, value)));
+    }
+
+    groups
+}
+
+/// Sorts `data` and returns its distinct values plus the values that appeared more than once.
+///
+/// Both lists are sorted and each value is listed at most once in each of them. After sorting,
+/// equal values sit next to each other, so one pass over the runs finds both.
+pub fn sort_and_find_duplicates<T: PartialOrd + Copy>(data: Vec<T>) -> (Vec<T>, Vec<T>) {
+    let sorted = merge_sort(data);
+
+    let mut unique: Vec<T> = Vec::new();
+    let mut duplicates: Vec<T> = Vec::new();
+    for value in sorted {
+        match unique.last() {
+            // The second element of a run is what marks the value as duplicated
+            Some(&last) if last == value => {
+                if duplicates.last() != Some(&value) {
+                    duplicates.push(value);
+                }
+            }
+            _ => unique.push(value),
+        }
+    }
+
+    (unique, duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::random_values;
+
+    #[test]
+    fn sort_grouped_groups_by_remainder_in_key_order() {
+        let groups = sort_grouped(vec![5, 3, 9, 4, 1, 6, 7, 2], |x| x % 3);
+        let groups: Vec<(i32, Vec<i32>)> = groups.into_iter().collect();
+        // Keys come out ascending, and every group keeps its input order
+        assert_eq!(groups, [(0, vec![3, 9, 6]), (1, vec![4, 1, 7]), (2, vec![5, 2])]);
+        assert!(sort_grouped(Vec::<i32>::new(), |x| x % 3).is_empty());
+    }
+
+    #[test]
+    fn sort_and_find_duplicates_reports_each_repeated_value_once() {
+        assert_eq!(sort_and_find_duplicates(vec![1, 2, 2, 3, 3, 3, 4]), (vec![1, 2, 3, 4], vec![2, 3]));
+        assert_eq!(sort_and_find_duplicates(vec![3, 1, 3, 2, 1, 3]), (vec![1, 2, 3], vec![1, 3]));
+        assert_eq!(sort_and_find_duplicates(vec![5, 4]), (vec![4, 5], vec![]));
+        assert_eq!(sort_and_find_duplicates(Vec::<i32>::new()), (vec![], vec![]));
+    }
+
+    #[test]
+    fn factorize_codes_map_back_to_the_data() {
+        let data = ["red", "green", "red", "blue", "green", "red"];
+        let (codes, vocab) = factorize(&data);
+        assert_eq!(vocab, ["blue", "green", "red"]);
+        assert_eq!(codes, [2, 1, 2, 0, 1, 2]);
+
+        for len in [0, 1, 50, 1000] {
+            let data = random_values(len, 20, len as u64 + 1);
+            let (codes, vocab) = factorize(&data);
+            assert!(vocab.windows(2).all(|pair| pair[0] < pair[1]), "vocabulary is sorted and distinct");
+            let rebuilt: Vec<i64> = codes.iter().map(|&code| vocab[code as usize]).collect();
+            assert_eq!(rebuilt, data);
+        }
+    }
+
+    #[test]
+    fn sort_and_reduce_folds_each_key_in_input_order() {
+        let sales = vec![("pear", 3), ("apple", 5), ("pear", 1), ("fig", 2), ("apple", 4)];
+        let totals = sort_and_reduce(sales.clone(), |sale| sale.0, 0, |total, sale| total + sale.1);
+        assert_eq!(totals, [("apple", 9), ("fig", 2), ("pear", 4)]);
+
+        // Collecting instead of summing shows the order reduce sees the elements in
+        let amounts = sort_and_reduce(sales, |sale| sale.0, Vec::new(), |mut seen, sale| {
+            seen.push(sale.1);
+            seen
+        });
+        assert_eq!(amounts, [("apple", vec![5, 4]), ("fig", vec![2]), ("pear", vec![3, 1])]);
+
+        assert!(sort_and_reduce(Vec::<i32>::new(), |&x| x, 0, |acc, x| acc + x).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+// BINARY MIN-HEAP
+// std's BinaryHeap needs `Ord`, which rules out floats, so the selection functions use this
+// small heap that only needs `PartialOrd`.
+
+pub(crate) struct MinHeap<T> {
+    items: Vec<T>,
+}
+
+impl<T: PartialOrd> MinHeap<T> {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        MinHeap { items: Vec::with_capacity(capacity) }
+    }
+
+    // Builds a heap out of `items` in O(n) by sifting down every parent, last one first
+    pub(crate) fn from_vec(items: Vec<T>) -> Self {
+        let mut heap = MinHeap { items };
+        for parent in (0..heap.items.len() / 2).rev() {
+            heap.sift_down(parent);
+        }
+        heap
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub(crate) fn peek(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    pub(crate) fn push(&mut self, item: T) {
+        self.items.push(item);
+        self.sift_up(self.items.len() - 1);
+    }
+
+    // Swaps the smallest element out for `item` in one sift, cheaper than pop followed by push
+    pub(crate) fn replace_min(&mut self, item: T) {
+        self.items[0] = item;
+        self.sift_down(0);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        if self.items.is_empty() {
+            return None;
+        }
+        // Move the last leaf to the root and let it sink back into place
+        let smallest = self.items.swap_remove(0);
+        self.sift_down(0);
+        Some(smallest)
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+
+    fn sift_up(&mut self, mut child: usize) {
+        while child > 0 {
+            let parent = (child - 1) / 2;
+            if self.items[child] >= self.items[parent] {
+                return;
+            }
+            self.items.swap(child, parent);
+            child = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut parent: usize) {
+        loop {
+            let mut smallest = parent;
+            for child in [2 * parent + 1, 2 * parent + 2] {
+                if child < self.items.len() && self.items[child] < self.items[smallest] {
+                    smallest = child;
+                }
+            }
+            if smallest == parent {
+                return;
+            }
+            self.items.swap(parent, smallest);
+            parent = smallest;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// JSON SORTING (feature "serde")
+
+use std::cmp::Ordering;
+
+use serde_json::{Number, Value};
+
+use crate::error::SortError;
+use crate::sequential::merge_sort_by;
+
+/// Parses a JSON array of numbers, sorts it (ascending) and serializes it back.
+///
+/// Numbers are compared by their exact value, even integers past 2^53 where `f64` can't tell
+/// neighbours apart, and equal values keep their input order. Integers are written back as
+/// integers, the rest in `serde_json`'s shortest float form (so `1e2` becomes `100.0` and `1.50`
+/// becomes `1.5`). Fails with `SortError::InvalidJson`,
+/// `SortError::NotAnArray` or `SortError::NotANumber` when the input isn't an array of numbers.
+pub fn sort_json_array(json: &str) -> Result<String, SortError> {
+    let parsed: Value = serde_json::from_str(json).map_err(|err| SortError::InvalidJson(err.to_string()))?;
+    let Value::Array(elements) = parsed else {
+        return Err(SortError::NotAnArray);
+    };
+
+    let mut numbers = Vec::with_capacity(elements.len());
+    for (index, element) in elements.into_iter().enumerate() {
+        let Value::Number(number) = element else {
+            return Err(SortError::NotANumber { index });
+        };
+        numbers.push(number);
+    }
+
+    let sorted: Vec<Value> = merge_sort_by(numbers, compare_numbers).into_iter().map(Value::Number).collect();
+    Ok(serde_json::to_string(&sorted).expect("a list of JSON values always serializes"))
+}
+
+// Orders two JSON numbers by their exact values (i64 and u64 together fit in an i128, and JSON
+// has no NaN or infinities). Going through f64 for mixed pairs would round large integers, and
+// the resulting order isn't even transitive, so merge sort could return unsorted output.
+fn compare_numbers(a: &Number, b: &Number) -> Ordering {
+    let as_integer = |number: &Number| number.as_i64().map(i128::from).or(number.as_u64().map(i128::from));
+    let as_float = |number: &Number| number.as_f64().expect("a JSON number always converts to f64");
+    match (as_integer(a), as_integer(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(a), None) => compare_integer_to_float(a, as_float(b)),
+        (None, Some(b)) => compare_integer_to_float(b, as_float(a)).reverse(),
+        (None, None) => as_float(a).partial_cmp(&as_float(b)).unwrap_or(Ordering::Equal),
+    }
+}
+
+// Compares an integer with a finite float exactly, using the float's floor. That floor is a whole
+// number, so casting it is exact (or saturates far outside the range of any JSON integer).
+fn compare_integer_to_float(integer: i128, float: f64) -> Ordering {
+    let floor = float.floor();
+    match integer.cmp(&(floor as i128)) {
+        // Same whole part, so only a fractional part can make the float bigger
+        Ordering::Equal if float > floor => Ordering::Less,
+        ordering => ordering,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_numbers_and_keeps_their_formatting() {
+        assert_eq!(sort_json_array("[3, -1.5, 2, 0, 10]").unwrap(), "[-1.5,0,2,3,10]");
+        assert_eq!(sort_json_array("[]").unwrap(), "[]");
+    }
+
+    #[test]
+    fn compares_large_integers_exactly() {
+        // Both of these are the same f64
+        let sorted = sort_json_array("[9007199254740993, 9007199254740992]").unwrap();
+        assert_eq!(sorted, "[9007199254740992,9007199254740993]");
+        assert_eq!(
+            sort_json_array("[18446744073709551615, -9223372036854775808, 9223372036854775807]").unwrap(),
+            "[-9223372036854775808,9223372036854775807,18446744073709551615]"
+        );
+    }
+
+    #[test]
+    fn compares_integers_and_floats_exactly() {
+        // 9007199254740992.0 equals the second integer, and both are below the first
+        assert_eq!(
+            sort_json_array("[9007199254740993, 9007199254740992.0, 9007199254740992]").unwrap(),
+            "[9007199254740992.0,9007199254740992,9007199254740993]"
+        );
+        assert_eq!(sort_json_array("[2, 1.5, -1, -1.5, 1, 2.0]").unwrap(), "[-1.5,-1,1,1.5,2,2.0]");
+        assert_eq!(
+            sort_json_array("[1e300, 18446744073709551615, -1e300, -9223372036854775808]").unwrap(),
+            "[-1e+300,-9223372036854775808,18446744073709551615,1e+300]"
+        );
+    }
+
+    #[test]
+    fn rewrites_floats_in_shortest_form() {
+        assert_eq!(sort_json_array("[1.50, 1e2]").unwrap(), "[1.5,100.0]");
+    }
+
+    #[test]
+    fn rejects_non_arrays_and_non_numbers() {
+        assert!(matches!(sort_json_array("[1, 2"), Err(SortError::InvalidJson(_))));
+        assert_eq!(sort_json_array("{\"a\": 1}"), Err(SortError::NotAnArray));
+        assert_eq!(sort_json_array("[1, \"two\", 3]"), Err(SortError::NotANumber { index: 1 }));
+    }
+}
@@ -0,0 +1,68 @@
+//! Sorting routines shared by the multithreaded sorting demo.
+//!
+//! Most functions work on any element type that is `PartialOrd + Copy`, so the same code
+//! sorts signed/unsigned integers and floats alike, as well as types like `std::time::Duration`
+//! or tuples of those. `merge_sort_owned` drops the `Copy` bound by moving elements, for owned
+//! types like `String`.
+//!
+//! ```
+//! use std::time::Duration;
+//! use multithreaded_sorting_rust::merge_sort;
+//!
+//! let sorted = merge_sort(vec![Duration::from_secs(3), Duration::from_millis(500)]);
+//! assert_eq!(sorted, [Duration::from_millis(500), Duration::from_secs(3)]);
+//! ```
+//!
+//! The parallel functions need the (default) `threads` feature and a target that can spawn
+//! threads. On `wasm32`, or with `default-features = false`, they quietly run on the calling
+//! thread instead: results are the same, but there is no speedup.
+
+mod adaptive;
+#[cfg(feature = "collation")]
+mod collation;
+mod config;
+mod error;
+mod grouping;
+mod heap;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod network;
+mod parallel;
+mod selection;
+mod sequential;
+mod soa;
+mod stats;
+#[cfg(test)]
+mod test_util;
+mod unstable;
+
+pub use adaptive::{
+    analyze, explain, smart_sort, sort_appended, sort_with_outcome, sorted_prefix_len,
+    sortedness, Algorithm, InputProfile, SortOutcome,
+};
+#[cfg(feature = "collation")]
+pub use collation::merge_sort_collated;
+pub use config::{Balancing, SortConfig};
+pub use error::SortError;
+pub use grouping::{factorize, sort_and_find_duplicates, sort_and_reduce, sort_grouped};
+#[cfg(feature = "serde")]
+pub use json::sort_json_array;
+#[cfg(feature = "mmap")]
+pub use mmap::sort_mmap;
+pub use network::sort_array;
+pub use parallel::{
+    merge_all_parallel, merge_concurrent, parallel_merge_sort_auto, parallel_sample_sort,
+    parallel_sort_scoped, sort_batch, sort_from_channel,
+};
+pub use selection::{lazy_sorted, sort_until, top_k_largest};
+pub use sequential::{
+    counting_sort, from_sorted_chunks, merge, merge_desc, merge_k_slices, merge_slices,
+    merge_sort, merge_sort_by, merge_sort_by_key, merge_sort_case_insensitive, merge_sort_deque,
+    merge_sort_desc, merge_sort_in, merge_sort_instrumented, merge_sort_owned, merge_sort_reuse,
+    natural_merge_sort,
+};
+pub use soa::{sort_permutation, sort_soa, Column};
+pub use stats::{quantiles, sort_with_extremes};
+pub use unstable::quick_sort;
@@ -1,26 +1,27 @@
-/// Overview
-/// This program is a template for how to split up global data and perform a computation-heavy task
-/// (like sorting) concurrently by spinning multiple threads.
-/// Basically a Divide and Conquer while avoiding data races.
-
-/// Problem
-/// Because static mutable variables are inherently unsafe due to potential data races,
-/// Rust does not directly allow mutable statics without an unsafe block.
-/// Also, the size of mutable static variables must be known at compile time, but this cannot
-/// be done with a vector since its size is dynamic.
-
-/// Solution
-/// 'Mutex' ensures that access to the array is synchronized, preventing data races in multithreaded contexts
-/// 'lazy_static!' allows to define SORTED_ARR as a static reference to a Mutex-protected array, providing safe, global mutable access.
-/// The array is initialized the first time it's accessed, avoiding the limitations of Rust's const-eval system for static initializers
-
-//// DEPENDENCIES AND LIBRARY IMPORTS
+//! Overview
+//! This program is a template for how to split up global data and perform a computation-heavy task
+//! (like sorting) concurrently by spinning multiple threads.
+//! Basically a Divide and Conquer while avoiding data races.
+//!
+//! Problem
+//! Because static mutable variables are inherently unsafe due to potential data races,
+//! Rust does not directly allow mutable statics without an unsafe block.
+//! Also, the size of mutable static variables must be known at compile time, but this cannot
+//! be done with a vector since its size is dynamic.
+//!
+//! Solution
+//! 'Mutex' ensures that access to the array is synchronized, preventing data races in multithreaded contexts
+//! 'lazy_static!' allows to define SORTED_ARR as a static reference to a Mutex-protected array, providing safe, global mutable access.
+//! The array is initialized the first time it's accessed, avoiding the limitations of Rust's const-eval system for static initializers
+
+// DEPENDENCIES AND LIBRARY IMPORTS
 extern crate lazy_static;
 use std::thread;
 use std::sync::{Mutex};
 use lazy_static::lazy_static;
+use multithreaded_sorting_rust::{merge, merge_sort};
 
-//// GLOBALS
+// GLOBALS
 // Immutable global array remains the same
 static ARR: [i32; 14] = [16, 26, 53, 44, 65, 36, 77, 89, 91, 106, 51, 62, 123, 69];
 // Mutable global empty array that will hold the sorted array
@@ -31,63 +32,7 @@ lazy_static! {
     static ref SORTED_ARR: Mutex<[i32; 14]> = Mutex::new([0; 14]);
 }
 
-// SORTING FUNCTIONS
-// Generic "T" is used with traits "Partial Order" and Copy.
-// This allows the sorting algorithm to work with integers (signed/unsigned) and floats
-
-// Helper function to recursively split the array
-fn merge_sort<T: PartialOrd + Copy>(data: Vec<T>) -> Vec<T> {
-    // Base case
-    if data.len() <= 1 {
-        return data;
-    }
-
-    // Get the midpoint
-    let middle = data.len()/2;
-
-    // Split the vector in half recursively until there is only one element
-    let left = merge_sort(data[..middle].to_vec());
-    let right = merge_sort(data[middle..].to_vec());
-
-    // Merge and sort the vector elements
-    merge(left, right)
-}
-
-// Sorting algorithm to merge two vectors into a single sorted vector
-fn merge<T: PartialOrd + Copy>(left: Vec<T>, right: Vec<T>) -> Vec<T> {
-    // Instantiate sorted vector we will return
-    let mut result = Vec::with_capacity(left.len() + right.len());
-
-    // 2 pointers to compare elements in each vector
-    // i - left vector
-    // j - right vector
-    let (mut i, mut j) = (0, 0);
-    // Loop continues as long as there are elements in both vectors that need to be compared and merged
-    while i < left.len() && j < right.len() {
-        if left[i] <= right[j] {
-            result.push(left[i]);
-            i+=1;
-        } else {
-            result.push(right[j]);
-            j+=1;
-        }
-    }
-
-    // Add elements left over from other vector
-    // We can assume the rest of the array is sorted
-    if i < left.len() {
-        result.extend_from_slice(&left[i..]);
-    }
-    if j < right.len() {
-        result.extend_from_slice(&right[j..]);
-    }
-
-    // return sorted vector
-    result
-}
-
-
-
+// The sorting functions themselves live in the library crate (src/sequential.rs)
 
 fn main() {
     // Split the array into 2 slices at middle index
@@ -0,0 +1,147 @@
+// MEMORY-MAPPED RECORD SORTING (feature "mmap")
+// Sorts a file of fixed-size records without reading it into a Vec: only one index per record
+// and a single record-sized buffer live on the heap, the records themselves stay in the mapping.
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+
+use memmap2::MmapMut;
+
+use crate::sequential::merge_sort_by;
+
+/// Sorts the fixed-size records of the file at `path` in place, by the key bytes
+/// `key_offset..key_offset + key_len` of each record.
+///
+/// Keys are compared as raw bytes (lexicographically), straight from the mapped memory. That's
+/// the natural order for text and for big-endian unsigned integers. Because nothing is ever read
+/// as a typed value there are no alignment requirements: `record_size`, `key_offset` and the
+/// file length can be any byte counts. Records with equal keys keep their order.
+///
+/// Fails with `InvalidInput` if the key doesn't fit in a record (or `record_size` is 0), and with
+/// `InvalidData` if the file length isn't a multiple of `record_size`.
+///
+/// The file must not be modified or truncated by anyone else while it's being sorted. A mapping
+/// can't prevent that, and doing so would change the memory out from under the sort.
+pub fn sort_mmap(path: &Path, record_size: usize, key_offset: usize, key_len: usize) -> io::Result<()> {
+    if record_size == 0 || key_offset.checked_add(key_len).is_none_or(|key_end| key_end > record_size) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "key does not fit inside a record"));
+    }
+
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    let len = usize::try_from(file.metadata()?.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file is too large to map"))?;
+    if len % record_size != 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "file length is not a multiple of the record size"));
+    }
+    // Mapping an empty file fails on some platforms, and there's nothing to sort anyway
+    if len == 0 {
+        return Ok(());
+    }
+
+    // SAFETY: the mapping is only valid while nobody else modifies or truncates the file,
+    // which is the caller's documented responsibility.
+    let mut map = unsafe { MmapMut::map_mut(&file)? };
+    let records = len / record_size;
+
+    // Sort record numbers instead of records, reading the keys directly from the mapping.
+    // order[i] is the record that belongs at position i.
+    let key = |record: usize| &map[record * record_size + key_offset..][..key_len];
+    let order = merge_sort_by((0..records).collect(), |&a, &b| key(a).cmp(key(b)));
+
+    // Apply the permutation one cycle at a time, so only a single record ever needs a temporary copy
+    let mut placed = vec![false; records];
+    let mut held = vec![0u8; record_size];
+    for start in 0..records {
+        if placed[start] || order[start] == start {
+            continue;
+        }
+
+        held.copy_from_slice(&map[start * record_size..][..record_size]);
+        let mut position = start;
+        loop {
+            placed[position] = true;
+            let source = order[position];
+            if source == start {
+                map[position * record_size..][..record_size].copy_from_slice(&held);
+                break;
+            }
+            map.copy_within(source * record_size..(source + 1) * record_size, position * record_size);
+            position = source;
+        }
+    }
+
+    map.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::test_util::random_values;
+
+    // A file in the temp directory that is removed again when the test is done with it
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn with_contents(name: &str, contents: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("sort_mmap_{}_{name}", std::process::id()));
+            fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn sorts_records_by_their_key_bytes() {
+        // 6-byte records: a 2-byte tag, then a 4-byte big-endian key
+        let keys = random_values(500, 50, 1);
+        let records: Vec<[u8; 6]> = keys
+            .iter()
+            .zip(0u16..)
+            .map(|(&key, tag)| {
+                let mut record = [0; 6];
+                record[..2].copy_from_slice(&tag.to_be_bytes());
+                record[2..].copy_from_slice(&(key as u32).to_be_bytes());
+                record
+            })
+            .collect();
+        let file = TempFile::with_contents("records", &records.concat());
+
+        sort_mmap(&file.0, 6, 2, 4).unwrap();
+
+        // Equal keys keep their order, so this is the same as a stable sort by key
+        let mut expected = records.clone();
+        expected.sort_by_key(|record| record[2..].to_vec());
+        assert_eq!(fs::read(&file.0).unwrap(), expected.concat());
+    }
+
+    #[test]
+    fn sorts_an_empty_file() {
+        let file = TempFile::with_contents("empty", &[]);
+        sort_mmap(&file.0, 4, 0, 4).unwrap();
+        assert!(fs::read(&file.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_layouts() {
+        let file = TempFile::with_contents("layout", b"abcdefg");
+        let error = |record_size, key_offset, key_len| {
+            sort_mmap(&file.0, record_size, key_offset, key_len).unwrap_err().kind()
+        };
+
+        assert_eq!(error(0, 0, 0), io::ErrorKind::InvalidInput);
+        assert_eq!(error(4, 2, 3), io::ErrorKind::InvalidInput);
+        assert_eq!(error(4, usize::MAX, 2), io::ErrorKind::InvalidInput);
+        // 7 bytes aren't a whole number of 2-byte records
+        assert_eq!(error(2, 0, 1), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read(&file.0).unwrap(), b"abcdefg");
+    }
+}
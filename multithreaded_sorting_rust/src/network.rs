@@ -0,0 +1,96 @@
+// FIXED-SIZE ARRAY SORTING
+// For tiny arrays a sorting network (a fixed list of compare-and-swap steps) beats any general
+// algorithm: no allocation, no recursion and no data-dependent loop bounds.
+
+use crate::adaptive::insertion_sort;
+
+// NETWORKS[n] is a minimal-comparison network for n elements: for every (i, j) pair in order,
+// swap arr[i] and arr[j] if they are out of order.
+const NETWORKS: [&[(usize, usize)]; 9] = [
+    &[],
+    &[],
+    &[(0, 1)],
+    &[(0, 2), (0, 1), (1, 2)],
+    &[(0, 2), (1, 3), (0, 1), (2, 3), (1, 2)],
+    &[(0, 3), (1, 4), (0, 2), (1, 3), (0, 1), (2, 4), (1, 2), (3, 4), (2, 3)],
+    &[(0, 5), (1, 3), (2, 4), (1, 2), (3, 4), (0, 3), (2, 5), (0, 1), (2, 3), (4, 5), (1, 2), (3, 4)],
+    &[
+        (0, 6), (2, 3), (4, 5), (0, 2), (1, 4), (3, 6), (0, 1), (2, 5), (3, 4), (1, 2), (4, 6), (2, 3),
+        (4, 5), (1, 2), (3, 4), (5, 6),
+    ],
+    &[
+        (0, 2), (1, 3), (4, 6), (5, 7), (0, 4), (1, 5), (2, 6), (3, 7), (0, 1), (2, 3), (4, 5), (6, 7),
+        (2, 4), (3, 5), (1, 4), (3, 6), (1, 2), (3, 4), (5, 6),
+    ],
+];
+
+/// Sorts a fixed-size array (ascending) on the stack, without any heap allocation.
+///
+/// Arrays of up to 8 elements go through an optimal sorting network; longer ones use insertion
+/// sort, which is still fine for the small sizes this is meant for (RGBA channels, small tuples).
+/// Sorting networks are not stable, so equal elements may be reordered.
+pub fn sort_array<T: PartialOrd + Copy, const N: usize>(mut arr: [T; N]) -> [T; N] {
+    match NETWORKS.get(N) {
+        Some(network) => {
+            for &(i, j) in network.iter() {
+                if arr[j] < arr[i] {
+                    arr.swap(i, j);
+                }
+            }
+        }
+        None => insertion_sort(&mut arr),
+    }
+    arr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{random_values, std_sorted};
+
+    // By the 0-1 principle, sorting every array of 0s and 1s proves a network sorts everything.
+    // Random values on top cover the insertion sort used past 8 elements.
+    fn assert_sorts_all_inputs<const N: usize>() {
+        for bits in 0u32..(1 << N) {
+            let arr: [u8; N] = std::array::from_fn(|i| ((bits >> i) & 1) as u8);
+            assert_eq!(sort_array(arr).to_vec(), std_sorted(&arr), "N = {N}, input {arr:?}");
+        }
+        for seed in 1..20 {
+            let arr: [i64; N] = random_values(N, 100, seed).try_into().unwrap();
+            assert_eq!(sort_array(arr).to_vec(), std_sorted(&arr), "N = {N}, input {arr:?}");
+        }
+    }
+
+    #[test]
+    fn sort_array_sorts_every_size_up_to_16() {
+        assert_sorts_all_inputs::<0>();
+        assert_sorts_all_inputs::<1>();
+        assert_sorts_all_inputs::<2>();
+        assert_sorts_all_inputs::<3>();
+        assert_sorts_all_inputs::<4>();
+        assert_sorts_all_inputs::<5>();
+        assert_sorts_all_inputs::<6>();
+        assert_sorts_all_inputs::<7>();
+        assert_sorts_all_inputs::<8>();
+        assert_sorts_all_inputs::<9>();
+        assert_sorts_all_inputs::<10>();
+        assert_sorts_all_inputs::<11>();
+        assert_sorts_all_inputs::<12>();
+        assert_sorts_all_inputs::<13>();
+        assert_sorts_all_inputs::<14>();
+        assert_sorts_all_inputs::<15>();
+        assert_sorts_all_inputs::<16>();
+    }
+
+    #[test]
+    fn networks_only_compare_positions_in_range() {
+        for (n, network) in NETWORKS.iter().enumerate() {
+            assert!(network.iter().all(|&(i, j)| i < j && j < n), "network for {n} elements");
+        }
+    }
+
+    #[test]
+    fn sort_array_handles_floats() {
+        assert_eq!(sort_array([0.5, -2.0, 3.25, 0.0]), [-2.0, 0.0, 0.5, 3.25]);
+    }
+}
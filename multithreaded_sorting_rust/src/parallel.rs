@@ -0,0 +1,785 @@
+// PARALLEL SORTING FUNCTIONS
+// These borrow the data with scoped threads instead of moving it into 'static closures,
+// which is what forces the demo binary to copy each half into an owned Vec first.
+
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::sync::mpsc::Receiver;
+use std::thread;
+
+use crate::adaptive::sortedness;
+use crate::sequential::{merge, merge_into, merge_k_slices, merge_sort, merge_sort_in};
+
+// Adaptive balancing weighs this many segments per thread before grouping them into chunks
+const SEGMENTS_PER_THREAD: usize = 8;
+// Sample sort picks its splitters from this many sampled elements per thread
+const OVERSAMPLING: usize = 32;
+// Threads can't be spawned on wasm32 or with the "threads" feature turned off. Every function in
+// this file then does its work on the calling thread instead, with the same results.
+const THREADS_AVAILABLE: bool = cfg!(all(feature = "threads", not(target_arch = "wasm32")));
+
+/// Sorts `data` in place (ascending) using up to `threads` scoped worker threads.
+///
+/// The slice is split into disjoint mutable chunks, each chunk is sorted on its own thread,
+/// and the sorted chunks are then merged back into `data`. Taking a `NonZeroUsize` means a
+/// thread count of zero can't be expressed at all: `NonZeroUsize::new(0)` is `None`.
+///
+/// Besides a few small lists of ranges, the only allocation is one scratch buffer as long as
+/// `data`, shared by all workers instead of each allocating its own. Every worker gets the part
+/// of it that lines up with its chunk, and the merge rounds go back and forth between `data` and
+/// the scratch buffer (like `merge_sort_in`), with neighbouring pairs of runs merged on their
+/// own threads.
+///
+/// ```
+/// use std::num::NonZeroUsize;
+/// use multithreaded_sorting_rust::parallel_sort_scoped;
+///
+/// let mut data = vec![5, 3, 9, 1, 7];
+/// parallel_sort_scoped(&mut data, NonZeroUsize::new(4).unwrap());
+/// assert_eq!(data, [1, 3, 5, 7, 9]);
+///
+/// // NonZeroUsize::MIN is one thread, the same as a plain sequential sort
+/// parallel_sort_scoped(&mut data, NonZeroUsize::MIN);
+/// ```
+///
+/// A bare `usize` doesn't compile, so a zero can't sneak in:
+///
+/// ```compile_fail
+/// let mut data = vec![2, 1];
+/// multithreaded_sorting_rust::parallel_sort_scoped(&mut data, 0usize);
+/// ```
+pub fn parallel_sort_scoped<T: PartialOrd + Copy + Send + Sync>(data: &mut [T], threads: NonZeroUsize) {
+    if data.len() <= 1 {
+        return;
+    }
+
+    let mut scratch = data.to_vec();
+    if !THREADS_AVAILABLE || threads.get() == 1 {
+        merge_sort_in(data, &mut scratch);
+        return;
+    }
+
+    let ranges = chunk_ranges(data.len(), threads);
+    #[cfg(test)]
+    let hook = delay::current();
+    thread::scope(|s| {
+        // Peel matching chunks off the front of both buffers, so each thread gets its own pair
+        let (mut rest, mut rest_scratch) = (&mut *data, scratch.as_mut_slice());
+        // The worker index is only read by the delay hook
+        #[cfg_attr(not(test), allow(clippy::unused_enumerate_index))]
+        for (_worker, range) in ranges.iter().enumerate() {
+            let (chunk, tail) = rest.split_at_mut(range.len());
+            let (chunk_scratch, scratch_tail) = rest_scratch.split_at_mut(range.len());
+            (rest, rest_scratch) = (tail, scratch_tail);
+            s.spawn(move || {
+                merge_sort_in(chunk, chunk_scratch);
+                #[cfg(test)]
+                delay::pause(hook, delay::Point::AfterChunkSort, _worker);
+            });
+        }
+    });
+
+    merge_runs_parallel(data, &mut scratch, ranges);
+}
+
+/// Sample sort: sorts `data` in place (ascending) on up to `threads` threads, where every thread
+/// ends up owning a disjoint range of values, so there is no merge at the end at all.
+///
+/// 1. An evenly spaced sample is sorted and `threads - 1` splitter values are picked from it.
+/// 2. Each thread sends the elements of its chunk to buckets by comparing them to the splitters.
+/// 3. Each thread gathers one bucket from all chunks, sorts it, and writes it to its final place.
+///
+/// Works best on uniformly spread values. Heavily skewed data (lots of copies of one value)
+/// still sorts correctly, but the bucket holding that value gets most of the work. Equal
+/// elements keep their input order.
+pub fn parallel_sample_sort<T: PartialOrd + Copy + Send + Sync>(data: &mut [T], threads: NonZeroUsize) {
+    // More threads than elements would only mean empty buckets, each with a thread of its own
+    let threads = threads.get().min(data.len());
+    if threads <= 1 || !THREADS_AVAILABLE {
+        let sorted = merge_sort(data.to_vec());
+        data.copy_from_slice(&sorted);
+        return;
+    }
+
+    // Splitters are the quantiles of a sorted sample, so the buckets come out about equally full
+    let step = data.len().div_ceil(threads.saturating_mul(OVERSAMPLING)).max(1);
+    let sample = merge_sort(data.iter().step_by(step).copied().collect());
+    let splitters: Vec<T> = (1..threads).map(|i| sample[i * sample.len() / threads]).collect();
+    let splitters = &splitters;
+
+    // Every chunk is split into the same set of buckets (in input order), on its own thread
+    let ranges = chunk_ranges(data.len(), NonZeroUsize::new(threads).unwrap());
+    let chunk_buckets: Vec<Vec<Vec<T>>> = thread::scope(|s| {
+        let handles: Vec<_> = ranges
+            .iter()
+            .map(|range| {
+                let chunk = &data[range.clone()];
+                s.spawn(move || {
+                    let mut buckets = vec![Vec::new(); splitters.len() + 1];
+                    for &value in chunk {
+                        // Bucket i holds the values between splitter i - 1 and splitter i
+                        buckets[splitters.partition_point(|splitter| *splitter <= value)].push(value);
+                    }
+                    buckets
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    // Bucket i's values all come before bucket i + 1's, so each one can be sorted straight into place
+    let chunk_buckets = &chunk_buckets;
+    thread::scope(|s| {
+        let mut rest = &mut *data;
+        for bucket in 0..=splitters.len() {
+            let len = chunk_buckets.iter().map(|buckets| buckets[bucket].len()).sum();
+            let (out, tail) = rest.split_at_mut(len);
+            rest = tail;
+            s.spawn(move || {
+                let gathered: Vec<T> =
+                    chunk_buckets.iter().flat_map(|buckets| buckets[bucket].iter().copied()).collect();
+                out.copy_from_slice(&merge_sort(gathered));
+            });
+        }
+    });
+}
+
+/// Same as `parallel_sort_scoped`, using one thread per core reported by
+/// `std::thread::available_parallelism` (or a single thread if that can't be determined).
+pub fn parallel_merge_sort_auto<T: PartialOrd + Copy + Send + Sync>(data: &mut [T]) {
+    let threads = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    parallel_sort_scoped(data, threads);
+}
+
+/// Merges sorted `runs` in a parallel binary tree: every round pairs up neighbouring runs and
+/// merges the pairs concurrently, until a single sorted run is left.
+///
+/// At most one thread per core (from `std::thread::available_parallelism`) is used per round,
+/// each merging its share of the pairs one after another, so many small runs don't turn into
+/// one OS thread per pair.
+pub fn merge_all_parallel<T: PartialOrd + Copy + Send>(runs: Vec<Vec<T>>) -> Vec<T> {
+    let workers = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    if !THREADS_AVAILABLE || workers.get() == 1 {
+        let slices: Vec<&[T]> = runs.iter().map(Vec::as_slice).collect();
+        return merge_k_slices(&slices);
+    }
+
+    merge_pairwise(runs, workers)
+}
+
+// The rounds of merge_all_parallel, on up to `workers` threads each
+fn merge_pairwise<T: PartialOrd + Copy + Send>(mut runs: Vec<Vec<T>>, workers: NonZeroUsize) -> Vec<T> {
+    while runs.len() > 1 {
+        // The unpaired last run (if any) is carried over to the next round untouched
+        let odd_one_out = if runs.len() % 2 == 1 { runs.pop() } else { None };
+        let mut pairs = Vec::with_capacity(runs.len() / 2);
+        let mut unpaired = runs.into_iter();
+        while let (Some(left), Some(right)) = (unpaired.next(), unpaired.next()) {
+            pairs.push((left, right));
+        }
+
+        // Consecutive pairs go to the same thread, so the merged runs come back in order
+        let per_worker = pairs.len().div_ceil(workers.get());
+        runs = thread::scope(|s| {
+            let mut handles = Vec::with_capacity(workers.get());
+            let mut pairs = pairs.into_iter().peekable();
+            while pairs.peek().is_some() {
+                let share: Vec<(Vec<T>, Vec<T>)> = pairs.by_ref().take(per_worker).collect();
+                handles.push(s.spawn(move || {
+                    share.into_iter().map(|(left, right)| merge(left, right)).collect::<Vec<_>>()
+                }));
+            }
+            handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+        });
+        runs.extend(odd_one_out);
+    }
+
+    runs.pop().unwrap_or_default()
+}
+
+/// Runs the `left` and `right` closures on two scoped threads and merges the sorted `Vec`s they
+/// return, once both have finished.
+///
+/// Both results must already be sorted ascending. Neither closure has to be `'static`, so they can
+/// borrow from the caller (e.g. each sorting one half of a shared slice).
+pub fn merge_concurrent<T: PartialOrd + Copy + Send>(
+    left: impl FnOnce() -> Vec<T> + Send,
+    right: impl FnOnce() -> Vec<T> + Send,
+) -> Vec<T> {
+    if !THREADS_AVAILABLE {
+        return merge(left(), right());
+    }
+
+    let (left, right) = thread::scope(|s| {
+        let left = s.spawn(left);
+        // The calling thread takes the right side itself instead of sitting idle in join
+        let right = right();
+        (left.join().unwrap(), right)
+    });
+    merge(left, right)
+}
+
+/// Sorts many independent arrays, handing whole arrays to each of up to `threads` threads.
+///
+/// Each thread sorts its share of the arrays one after another, so thread overhead is paid once
+/// per thread instead of once per array. That's the right granularity for lots of small arrays.
+/// The arrays come back in the same order they were given.
+pub fn sort_batch<T: PartialOrd + Copy + Send>(mut batches: Vec<Vec<T>>, threads: NonZeroUsize) -> Vec<Vec<T>> {
+    if batches.is_empty() {
+        return batches;
+    }
+    if !THREADS_AVAILABLE {
+        return batches.into_iter().map(merge_sort).collect();
+    }
+
+    let per_thread = batches.len().div_ceil(threads.get());
+    thread::scope(|s| {
+        for share in batches.chunks_mut(per_thread) {
+            s.spawn(move || {
+                for batch in share {
+                    *batch = merge_sort(std::mem::take(batch));
+                }
+            });
+        }
+    });
+
+    batches
+}
+
+/// Receives values from `rx` until every sender has been dropped, then returns them sorted.
+///
+/// Everything received is buffered in memory until the channel closes, so memory grows with
+/// the total number of values sent. Sorting only starts once the last value has arrived.
+pub fn sort_from_channel<T: PartialOrd + Copy + Send>(rx: Receiver<T>) -> Vec<T> {
+    // iter() blocks for each value and ends once the channel is closed and drained
+    let received: Vec<T> = rx.iter().collect();
+    merge_sort(received)
+}
+
+// Splits 0..len into min(len, parts) non-empty contiguous ranges that cover it exactly once.
+// Every range gets len / parts elements and the first len % parts ranges get one more, so no two
+// ranges differ by more than one element. Nothing is ever multiplied, so this can't overflow.
+pub(crate) fn chunk_ranges(len: usize, parts: NonZeroUsize) -> Vec<Range<usize>> {
+    let parts = parts.get().min(len);
+    if parts == 0 {
+        return Vec::new();
+    }
+
+    let (base, extra) = (len / parts, len % parts);
+    let mut start = 0;
+    (0..parts)
+        .map(|part| {
+            let end = start + base + usize::from(part < extra);
+            let range = start..end;
+            start = end;
+            range
+        })
+        .collect()
+}
+
+// Splits 0..data.len() into at most `parts` contiguous ranges of roughly equal sorting work.
+//
+// The input is cut into small segments, and each one is weighted by an estimate of its cost:
+// `len * (1 + disorder * log2(len))`, where disorder is 0 for a sorted segment and 1 for a random
+// one (from its sortedness). Segments are then grouped so every range gets about the same total weight.
+pub(crate) fn balanced_ranges<T: PartialOrd>(data: &[T], parts: NonZeroUsize) -> Vec<Range<usize>> {
+    let segments = chunk_ranges(data.len(), parts.saturating_mul(NonZeroUsize::new(SEGMENTS_PER_THREAD).unwrap()));
+    let weights: Vec<f64> = segments
+        .iter()
+        .map(|segment| {
+            let len = segment.len() as f64;
+            // Random data scores about 0.5, so stretch that to a full disorder of 1.0
+            let disorder = ((1.0 - sortedness(&data[segment.clone()])) * 2.0).min(1.0);
+            len * (1.0 + disorder * len.log2())
+        })
+        .collect();
+
+    let total: f64 = weights.iter().sum();
+    let target = total / parts.get() as f64;
+
+    // Close the current range as soon as it has collected its share of the weight
+    let mut ranges = Vec::with_capacity(parts.get());
+    let (mut start, mut collected) = (0, 0.0);
+    for (segment, weight) in segments.iter().zip(weights) {
+        collected += weight;
+        if collected >= target * (ranges.len() + 1) as f64 && ranges.len() + 1 < parts.get() {
+            ranges.push(start..segment.end);
+            start = segment.end;
+        }
+    }
+    if start < data.len() {
+        ranges.push(start..data.len());
+    }
+
+    ranges
+}
+
+// Sorts every range of `data` on its own scoped thread with `sort`, then merges the sorted ranges
+// back into `data`. The ranges must be contiguous and cover the whole slice.
+// With `pin_threads`, worker i is pinned to core i (see pin_to_core).
+pub(crate) fn sort_ranges_parallel<T, F>(data: &mut [T], ranges: &[Range<usize>], pin_threads: bool, sort: F)
+where
+    T: PartialOrd + Copy + Send + Sync,
+    F: Fn(Vec<T>) -> Vec<T> + Sync,
+{
+    if THREADS_AVAILABLE {
+        sort_ranges_on_threads(data, ranges, pin_threads, &sort);
+    } else {
+        for range in ranges {
+            let sorted = sort(data[range.clone()].to_vec());
+            data[range.clone()].copy_from_slice(&sorted);
+        }
+    }
+
+    #[cfg(test)]
+    delay::pause(delay::current(), delay::Point::BeforeMerge, 0);
+    let runs: Vec<&[T]> = ranges.iter().map(|range| &data[range.clone()]).collect();
+    let sorted = merge_k_slices(&runs);
+    data.copy_from_slice(&sorted);
+}
+
+// The threaded half of sort_ranges_parallel: one scoped thread per range
+fn sort_ranges_on_threads<T, F>(data: &mut [T], ranges: &[Range<usize>], pin_threads: bool, sort: &F)
+where
+    T: PartialOrd + Copy + Send + Sync,
+    F: Fn(Vec<T>) -> Vec<T> + Sync,
+{
+    #[cfg(test)]
+    let hook = delay::current();
+    thread::scope(|s| {
+        // Peel each range off the front, so every thread gets its own disjoint slice
+        let mut rest = &mut *data;
+        for (worker, range) in ranges.iter().enumerate() {
+            let (chunk, tail) = rest.split_at_mut(range.len());
+            rest = tail;
+            s.spawn(move || {
+                if pin_threads {
+                    pin_to_core(worker);
+                }
+                let sorted = sort(chunk.to_vec());
+                chunk.copy_from_slice(&sorted);
+                #[cfg(test)]
+                delay::pause(hook, delay::Point::AfterChunkSort, worker);
+            });
+        }
+    });
+}
+
+// Merges the sorted, contiguous `runs` of `data` into one, a round at a time: each round merges
+// every neighbouring pair of runs on its own thread into the other buffer (`data` or `scratch`,
+// which must be as long as `data`). An unpaired last run is copied over as is.
+fn merge_runs_parallel<T>(data: &mut [T], scratch: &mut [T], mut runs: Vec<Range<usize>>)
+where
+    T: PartialOrd + Copy + Send + Sync,
+{
+    #[cfg(test)]
+    let hook = delay::current();
+    // Tracks which of the two buffers currently holds the sorted runs
+    let mut runs_in_data = true;
+    while runs.len() > 1 {
+        let (src, dst) = if runs_in_data { (&*data, &mut *scratch) } else { (&*scratch, &mut *data) };
+        let mut merged = Vec::with_capacity(runs.len().div_ceil(2));
+        thread::scope(|s| {
+            let mut rest = dst;
+            #[cfg_attr(not(test), allow(clippy::unused_enumerate_index))]
+            for (_worker, pair) in runs.chunks(2).enumerate() {
+                let range = pair[0].start..pair[pair.len() - 1].end;
+                let (out, tail) = rest.split_at_mut(range.len());
+                rest = tail;
+                match pair {
+                    [left, right] => {
+                        let (left, right) = (&src[left.clone()], &src[right.clone()]);
+                        s.spawn(move || {
+                            #[cfg(test)]
+                            delay::pause(hook, delay::Point::BeforeMerge, _worker);
+                            merge_into(left, right, out);
+                        });
+                    }
+                    _ => out.copy_from_slice(&src[range.clone()]),
+                }
+                merged.push(range);
+            }
+        });
+
+        runs = merged;
+        runs_in_data = !runs_in_data;
+    }
+
+    if !runs_in_data {
+        data.copy_from_slice(scratch);
+    }
+}
+
+// Best-effort pinning of the current thread to a core (wrapping around if there are more workers
+// than cores). Does nothing without the "affinity" feature or where the platform doesn't support it.
+fn pin_to_core(worker: usize) {
+    #[cfg(feature = "affinity")]
+    if let Some(cores) = core_affinity::get_core_ids().filter(|cores| !cores.is_empty()) {
+        core_affinity::set_for_current(cores[worker % cores.len()]);
+    }
+    #[cfg(not(feature = "affinity"))]
+    let _ = worker;
+}
+
+// Test-only hooks for forcing particular interleavings of the worker threads. Tests install a
+// hook for the current thread with `delay::with_hook`; the threaded functions above pick it up
+// when they start and call it from their workers at each `Point`, where it can sleep or wait on
+// other workers. None of this (or the calls to it) exists outside of test builds.
+#[cfg(test)]
+mod delay {
+    use std::cell::Cell;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(super) enum Point {
+        // A worker has just finished sorting its chunk
+        AfterChunkSort,
+        // A worker is about to merge a pair of runs (or the calling thread, all of the runs)
+        BeforeMerge,
+    }
+
+    // Called with the point and the index of the worker that reached it
+    pub(super) type Hook = fn(Point, usize);
+
+    thread_local! {
+        static HOOK: Cell<Option<Hook>> = const { Cell::new(None) };
+    }
+
+    // Runs `f` with `hook` installed for the current thread, so sorts started inside it delay
+    pub(super) fn with_hook<R>(hook: Hook, f: impl FnOnce() -> R) -> R {
+        HOOK.with(|slot| slot.set(Some(hook)));
+        let result = f();
+        HOOK.with(|slot| slot.set(None));
+        result
+    }
+
+    // The hook installed on the calling thread, to be handed to its workers
+    pub(super) fn current() -> Option<Hook> {
+        HOOK.with(Cell::get)
+    }
+
+    pub(super) fn pause(hook: Option<Hook>, point: Point, worker: usize) {
+        if let Some(hook) = hook {
+            hook(point, worker);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Condvar, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_util::{keyed, random_values, stable_sorted, std_sorted, time_ms, Keyed};
+
+    fn threads(count: usize) -> NonZeroUsize {
+        NonZeroUsize::new(count).unwrap()
+    }
+
+    #[test]
+    fn parallel_sort_scoped_sorts_a_borrowed_slice() {
+        for count in [1, 2, 3, 4, 7, 16] {
+            for len in [0, 1, 2, 5, 64, 1001] {
+                let mut data = random_values(len + 4, 1000, len as u64 + 1);
+                let expected = std_sorted(&data[2..len + 2]);
+
+                // Only the middle is borrowed, and the surrounding elements must stay untouched
+                let outside = (data[..2].to_vec(), data[len + 2..].to_vec());
+                parallel_sort_scoped(&mut data[2..len + 2], threads(count));
+                assert_eq!(data[2..len + 2], expected, "{count} threads, len {len}");
+                assert_eq!((data[..2].to_vec(), data[len + 2..].to_vec()), outside);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_merge_sort_auto_sorts_with_the_available_threads() {
+        for len in [0, 1, 3, 10_000] {
+            let mut data = random_values(len, 500, len as u64 + 2);
+            let expected = std_sorted(&data);
+            parallel_merge_sort_auto(&mut data);
+            assert_eq!(data, expected, "len {len}");
+        }
+    }
+
+    #[test]
+    fn parallel_sort_scoped_is_stable() {
+        let values = random_values(2000, 5, 2);
+        let mut data = keyed(&values);
+        parallel_sort_scoped(&mut data, threads(6));
+        assert_eq!(data, stable_sorted(&values));
+    }
+
+    #[test]
+    fn sort_from_channel_sorts_everything_a_producer_sent() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let values = random_values(1000, 10_000, 12);
+        let sent = values.clone();
+        let producer = thread::spawn(move || {
+            for value in sent {
+                tx.send(value).unwrap();
+            }
+            // Dropping the last sender closes the channel, which ends sort_from_channel's loop
+        });
+
+        assert_eq!(sort_from_channel(rx), std_sorted(&values));
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn sort_from_channel_handles_several_producers_and_none() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let producers: Vec<_> = (0..4)
+            .map(|producer| {
+                let tx = tx.clone();
+                thread::spawn(move || (0..100).for_each(|i| tx.send(i * 4 + producer).unwrap()))
+            })
+            .collect();
+        drop(tx);
+
+        assert_eq!(sort_from_channel(rx), (0..400).collect::<Vec<_>>());
+        producers.into_iter().for_each(|producer| producer.join().unwrap());
+
+        let (tx, rx) = std::sync::mpsc::channel::<i32>();
+        drop(tx);
+        assert!(sort_from_channel(rx).is_empty());
+    }
+
+    #[test]
+    fn sort_batch_sorts_every_array_in_place_order() {
+        // More arrays than threads, fewer arrays than threads, and arrays of every size
+        let batches: Vec<Vec<i64>> = (0..37).map(|i| random_values(i * 7, 100, i as u64 + 1)).collect();
+        let expected: Vec<Vec<i64>> = batches.iter().map(|batch| std_sorted(batch)).collect();
+        for n in [1, 4, 64] {
+            assert_eq!(sort_batch(batches.clone(), threads(n)), expected, "{n} threads");
+        }
+        assert!(sort_batch(Vec::<Vec<i64>>::new(), threads(4)).is_empty());
+    }
+
+    // Chunk order the delay hook below has seen workers finish in, and the signal it waits on
+    static FINISHED: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+    static FINISHED_CHANGED: Condvar = Condvar::new();
+    const CHUNKS: usize = 8;
+
+    // Makes the chunk workers finish last to first (each waits until every later chunk is done),
+    // and holds the leftmost merges back the longest, so the right-hand pairs merge first
+    fn finish_in_reverse(point: delay::Point, worker: usize) {
+        match point {
+            delay::Point::AfterChunkSort => {
+                let mut finished = FINISHED.lock().unwrap();
+                while finished.len() != CHUNKS - 1 - worker {
+                    finished = FINISHED_CHANGED.wait(finished).unwrap();
+                }
+                finished.push(worker);
+                FINISHED_CHANGED.notify_all();
+            }
+            delay::Point::BeforeMerge => {
+                thread::sleep(Duration::from_millis(2 * CHUNKS.saturating_sub(worker) as u64));
+            }
+        }
+    }
+
+    #[test]
+    fn sorts_stay_correct_when_workers_finish_in_reverse() {
+        let values = random_values(10_000, 100, 23);
+        let sorts: [fn(&mut [Keyed]); 2] = [
+            |data| parallel_sort_scoped(data, threads(CHUNKS)),
+            |data| sort_ranges_parallel(data, &chunk_ranges(data.len(), threads(CHUNKS)), false, merge_sort),
+        ];
+        for sort in sorts {
+            FINISHED.lock().unwrap().clear();
+            let mut data = keyed(&values);
+            delay::with_hook(finish_in_reverse, || sort(&mut data));
+
+            assert_eq!(data, stable_sorted(&values));
+            if THREADS_AVAILABLE {
+                assert!(FINISHED.lock().unwrap().iter().copied().eq((0..CHUNKS).rev()));
+            }
+        }
+    }
+
+    // Benchmark, so not part of the normal run:
+    // cargo test --release --lib -- --ignored --test-threads=1 --nocapture sort_batch_vs
+    #[test]
+    #[ignore]
+    fn sort_batch_vs_a_parallel_sort_per_array() {
+        let workers = threads(4);
+        let batches: Vec<Vec<i64>> = (0..20_000).map(|i| random_values(50, 1000, i + 1)).collect();
+
+        let copy = batches.clone();
+        let (batched, batch_ms) = time_ms(|| sort_batch(copy, workers));
+        let (one_by_one, each_ms) = time_ms(|| {
+            let mut batches = batches;
+            for batch in &mut batches {
+                parallel_sort_scoped(batch, workers);
+            }
+            batches
+        });
+        assert_eq!(batched, one_by_one);
+
+        println!("20000 arrays of 50: sort_batch {batch_ms:.1} ms, one by one {each_ms:.1} ms");
+    }
+
+    #[test]
+    fn merge_concurrent_merges_two_sorted_halves() {
+        for len in [0, 1, 2, 9, 1000] {
+            let data = random_values(len, 1000, len as u64 + 3);
+            // Each closure borrows its half of `data`, no 'static or move of the whole Vec needed
+            let (left, right) = data.split_at(len / 2);
+            let merged = merge_concurrent(|| merge_sort(left.to_vec()), || merge_sort(right.to_vec()));
+            assert_eq!(merged, std_sorted(&data), "len {len}");
+        }
+    }
+
+    #[test]
+    fn merge_concurrent_keeps_left_before_right_on_ties() {
+        let merged = merge_concurrent(|| vec![Keyed(1, 0), Keyed(2, 1)], || vec![Keyed(1, 2), Keyed(2, 3)]);
+        assert_eq!(merged, [Keyed(1, 0), Keyed(1, 2), Keyed(2, 1), Keyed(2, 3)]);
+    }
+
+    #[test]
+    fn chunk_ranges_tile_the_input_exactly() {
+        for len in 0..100 {
+            for parts in 1..16 {
+                let ranges = chunk_ranges(len, threads(parts));
+                assert_eq!(ranges.len(), parts.min(len), "len {len}, parts {parts}");
+
+                // Each range starts where the previous one ended, so there are no gaps or overlaps
+                let mut next = 0;
+                for range in &ranges {
+                    assert_eq!(range.start, next, "len {len}, parts {parts}");
+                    assert!(!range.is_empty(), "len {len}, parts {parts}");
+                    next = range.end;
+                }
+                assert_eq!(next, len, "len {len}, parts {parts}");
+
+                let sizes: Vec<usize> = ranges.iter().map(|range| range.len()).collect();
+                if let (Some(smallest), Some(largest)) = (sizes.iter().min(), sizes.iter().max()) {
+                    assert!(largest - smallest <= 1, "len {len}, parts {parts}: {sizes:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_ranges_cannot_overflow() {
+        let ranges = chunk_ranges(usize::MAX, threads(7));
+        assert_eq!(ranges.last().unwrap().end, usize::MAX);
+        assert_eq!(chunk_ranges(3, NonZeroUsize::MAX), [0..1, 1..2, 2..3]);
+    }
+
+    #[test]
+    fn balanced_ranges_give_sorted_regions_bigger_ranges() {
+        // Sorted first half, random second half
+        let mut data: Vec<i64> = (0..40_000).collect();
+        data.extend(random_values(40_000, 1_000_000, 17));
+
+        let ranges = balanced_ranges(&data, threads(4));
+        assert!(ranges.len() <= 4, "{ranges:?}");
+        assert_eq!(ranges.first().unwrap().start, 0);
+        assert_eq!(ranges.last().unwrap().end, data.len());
+        assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start), "{ranges:?}");
+        // The cheap sorted half mostly lands in the first range
+        assert!(ranges[0].len() > 2 * ranges.last().unwrap().len(), "{ranges:?}");
+
+        assert!(balanced_ranges::<i64>(&[], threads(4)).is_empty());
+        assert_eq!(balanced_ranges(&[5, 1], threads(4)).last().unwrap().end, 2);
+    }
+
+    // Sorted runs of random lengths (including empty ones)
+    fn random_runs(count: usize, seed: u64) -> Vec<Vec<i64>> {
+        let lens = random_values(count, 40, seed);
+        lens.iter()
+            .zip(0..)
+            .map(|(&len, run_seed)| std_sorted(&random_values(len as usize, 1000, run_seed + 1)))
+            .collect()
+    }
+
+    #[test]
+    fn merge_all_parallel_agrees_with_merge_k_slices() {
+        for count in [0, 1, 2, 3, 10, 33] {
+            let runs = random_runs(count, count as u64 + 1);
+            let slices: Vec<&[i64]> = runs.iter().map(Vec::as_slice).collect();
+            let expected = merge_k_slices(&slices);
+            assert_eq!(merge_all_parallel(runs.clone()), expected);
+            // The threaded rounds directly, whatever the core count of the machine running this
+            for workers in [1, 2, 3, 8] {
+                assert_eq!(merge_pairwise(runs.clone(), threads(workers)), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_all_parallel_handles_many_tiny_runs() {
+        // One thread per pair would be 50000 threads in the first round alone
+        let values = random_values(100_000, 1_000_000, 9);
+        let runs: Vec<Vec<i64>> = values.iter().map(|&value| vec![value]).collect();
+        assert_eq!(merge_pairwise(runs.clone(), threads(4)), std_sorted(&values));
+        assert_eq!(merge_all_parallel(runs), std_sorted(&values));
+    }
+
+    #[test]
+    fn sample_sort_sorts_uniform_and_skewed_data() {
+        for count in [2, 3, 4, 8] {
+            for len in [0, 1, 2, 3, 7, 100, 5000] {
+                let mut uniform = random_values(len, 1_000_000, len as u64);
+                let expected = std_sorted(&uniform);
+                parallel_sample_sort(&mut uniform, threads(count));
+                assert_eq!(uniform, expected);
+
+                // Nine elements in ten are the same value, so one bucket gets nearly everything
+                let mut skewed: Vec<i64> =
+                    random_values(len, 100, 7).into_iter().map(|x| if x % 10 == 0 { x } else { 42 }).collect();
+                let expected = std_sorted(&skewed);
+                parallel_sample_sort(&mut skewed, threads(count));
+                assert_eq!(skewed, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn sample_sort_is_stable() {
+        let values = random_values(3000, 7, 1);
+        let mut data = keyed(&values);
+        parallel_sample_sort(&mut data, threads(4));
+        assert_eq!(data, stable_sorted(&values));
+    }
+
+    // Benchmark, so not part of the normal run:
+    // cargo test --release --lib -- --ignored --test-threads=1 --nocapture sample_sort_vs
+    #[test]
+    #[ignore]
+    fn sample_sort_vs_chunk_and_merge() {
+        // At least a few threads even on a small machine, so both sorts take their parallel path
+        let workers = thread::available_parallelism().map_or(4, NonZeroUsize::get).max(4);
+        let workers = threads(workers);
+        let uniform = random_values(8_000_000, i64::MAX, 31);
+        // A tenth of the values are one repeated value, the rest uniform
+        let skewed: Vec<i64> =
+            uniform.iter().enumerate().map(|(i, &value)| if i % 10 == 0 { 42 } else { value }).collect();
+
+        println!("{workers} threads");
+        for (name, data) in [("uniform", uniform), ("skewed", skewed)] {
+            let mut sampled = data.clone();
+            let ((), sample_ms) = time_ms(|| parallel_sample_sort(&mut sampled, workers));
+            let mut merged = data;
+            let ((), merge_ms) = time_ms(|| parallel_sort_scoped(&mut merged, workers));
+            assert_eq!(sampled, merged);
+
+            println!("{name}: sample sort {sample_ms:.1} ms, chunk and merge {merge_ms:.1} ms");
+        }
+    }
+
+    #[test]
+    fn sample_sort_caps_threads_at_the_input_length() {
+        // Neither overflows computing the sample step nor spawns a thread per requested worker
+        let mut data = vec![3, 1, 2];
+        parallel_sample_sort(&mut data, NonZeroUsize::MAX);
+        assert_eq!(data, [1, 2, 3]);
+
+        let mut data = random_values(50, 10, 3);
+        let expected = std_sorted(&data);
+        parallel_sample_sort(&mut data, threads(20_000));
+        assert_eq!(data, expected);
+    }
+}
@@ -0,0 +1,141 @@
+// SELECTION FUNCTIONS
+// Finding a few elements of the sorted order without paying for the whole sort.
+
+use crate::adaptive::{insertion_sort, INSERTION_LEN};
+use crate::heap::MinHeap;
+use crate::sequential::merge_sort_desc;
+use crate::unstable::{heap_sort, partition};
+
+/// Returns the `k` largest elements of `data`, largest first (the leaderboard case).
+///
+/// Keeps a min-heap of the best `k` seen so far, so it takes O(n log k) time and O(k) extra
+/// memory instead of sorting everything. For `k >= data.len()` it's just a full descending sort.
+pub fn top_k_largest<T: PartialOrd + Copy>(data: Vec<T>, k: usize) -> Vec<T> {
+    if k >= data.len() {
+        return merge_sort_desc(data);
+    }
+
+    let mut best = MinHeap::with_capacity(k);
+    for value in data {
+        if best.len() < k {
+            best.push(value);
+        } else if best.peek().is_some_and(|&smallest| value > smallest) {
+            // Anything bigger than the smallest of the current top k pushes it out
+            best.replace_min(value);
+        }
+    }
+
+    merge_sort_desc(best.into_vec())
+}
+
+/// Returns an iterator over `data` in ascending order that only sorts as far as it is consumed.
+///
+/// The elements are turned into a min-heap up front in O(n), and each `next` pops the smallest
+/// one in O(log n). So `lazy_sorted(data).take(k)` costs O(n + k log n), well under a full sort
+/// for small `k`, while consuming everything comes to O(n log n) like `merge_sort`. Unlike
+/// `merge_sort`, equal elements don't necessarily come out in their input order.
+pub fn lazy_sorted<T: PartialOrd + Copy>(data: Vec<T>) -> impl Iterator<Item = T> {
+    let mut heap = MinHeap::from_vec(data);
+    std::iter::from_fn(move || heap.pop())
+}
+
+/// Sorts `data` only as far as needed to find the smallest element satisfying `predicate`.
+///
+/// Returns the partially sorted data and the found element's position, or `None` (with the data
+/// fully sorted) if no element qualifies. Everything up to and including that position is in
+/// its final sorted order. The rest of the vector is only partitioned, not sorted.
+///
+/// Merge sort can't do this, because it doesn't settle any position until the very last merge.
+/// So this uses quicksort partitioning instead, and always finishes the leftmost part first:
+/// positions become final from left to right, each one is checked as soon as it is. Like
+/// `quick_sort`, it is not stable, and it falls back to heap sort on ranges that keep
+/// partitioning badly.
+pub fn sort_until<T, P>(mut data: Vec<T>, mut predicate: P) -> (Vec<T>, Option<usize>)
+where
+    T: PartialOrd + Copy,
+    P: FnMut(&T) -> bool,
+{
+    let depth_limit = 2 * (usize::BITS - data.len().leading_zeros());
+
+    // Ranges still to sort, leftmost on top. Everything left of the top range is already final.
+    let mut pending = vec![(0..data.len(), depth_limit)];
+    while let Some((range, depth)) = pending.pop() {
+        if range.len() > INSERTION_LEN && depth > 0 {
+            let pivot = range.start + partition(&mut data[range.clone()]);
+            pending.push((pivot + 1..range.end, depth - 1));
+            // The pivot is in its final place, so it gets checked on its own between the two sides
+            pending.push((pivot..pivot + 1, depth - 1));
+            pending.push((range.start..pivot, depth - 1));
+            continue;
+        }
+
+        // Finish this range, which makes all of its positions final, then check them in order
+        if range.len() > INSERTION_LEN {
+            heap_sort(&mut data[range.clone()]);
+        } else {
+            insertion_sort(&mut data[range.clone()]);
+        }
+        if let Some(offset) = data[range.clone()].iter().position(&mut predicate) {
+            return (data, Some(range.start + offset));
+        }
+    }
+
+    (data, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{random_values, std_sorted};
+
+    #[test]
+    fn top_k_largest_returns_the_leaderboard() {
+        assert_eq!(top_k_largest(vec![1, 5, 2, 8, 3], 2), [8, 5]);
+        assert_eq!(top_k_largest(vec![1, 5, 2, 8, 3], 0), Vec::<i32>::new());
+        // k past the end is the whole input, descending
+        assert_eq!(top_k_largest(vec![1, 5, 2], 9), [5, 2, 1]);
+        assert_eq!(top_k_largest(vec![0.5, -1.0, 2.5, 2.5], 3), [2.5, 2.5, 0.5]);
+    }
+
+    #[test]
+    fn top_k_largest_agrees_with_a_full_sort() {
+        for len in [0, 1, 10, 500] {
+            let data = random_values(len, 50, len as u64 + 1);
+            let mut descending = data.clone();
+            descending.sort_by(|a, b| b.cmp(a));
+            for k in [1, 3, 10, 499] {
+                assert_eq!(top_k_largest(data.clone(), k), descending[..k.min(len)], "len {len}, k {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn sort_until_stops_once_the_position_is_final() {
+        let data = random_values(1000, 10_000, 7);
+        let sorted = std_sorted(&data);
+        for threshold in [-10_001, -5000, 0, 9_000] {
+            let (partial, found) = sort_until(data.clone(), |&x| x > threshold);
+            let expected = sorted.iter().position(|&x| x > threshold).unwrap();
+            assert_eq!(found, Some(expected), "threshold {threshold}");
+            // Everything up to the match is final, the rest is just the remaining elements
+            assert_eq!(partial[..=expected], sorted[..=expected]);
+            assert_eq!(std_sorted(&partial), sorted);
+        }
+    }
+
+    #[test]
+    fn sort_until_sorts_everything_when_nothing_matches() {
+        let data = random_values(500, 100, 3);
+        assert_eq!(sort_until(data.clone(), |&x| x > 1000), (std_sorted(&data), None));
+        assert_eq!(sort_until(Vec::<i32>::new(), |_| true), (vec![], None));
+    }
+
+    #[test]
+    fn lazy_sorted_yields_ascending_on_demand() {
+        let data = random_values(2000, 500, 11);
+        assert_eq!(lazy_sorted(data.clone()).take(5).collect::<Vec<_>>(), std_sorted(&data)[..5]);
+        assert_eq!(lazy_sorted(data.clone()).collect::<Vec<_>>(), std_sorted(&data));
+        assert_eq!(lazy_sorted(vec![2.5, -0.5, 1.0]).collect::<Vec<_>>(), [-0.5, 1.0, 2.5]);
+        assert_eq!(lazy_sorted(Vec::<u8>::new()).next(), None);
+    }
+}
@@ -0,0 +1,650 @@
+// SORTING FUNCTIONS
+// Generic "T" is used with traits "Partial Order" and Copy.
+// This allows the sorting algorithm to work with integers (signed/unsigned) and floats
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use crate::error::SortError;
+
+/// Recursively splits the vector in half and merges the sorted halves back together (ascending).
+///
+/// Wrapping elements in `std::cmp::Reverse` sorts them descending: `Reverse<T>` is `PartialOrd + Copy`
+/// whenever `T` is. Unlike reversing afterwards, equal elements keep their input order.
+///
+/// ```
+/// use std::cmp::Reverse;
+/// use multithreaded_sorting_rust::merge_sort;
+///
+/// assert_eq!(merge_sort(vec![Reverse(1), Reverse(3), Reverse(2)]), [Reverse(3), Reverse(2), Reverse(1)]);
+/// ```
+pub fn merge_sort<T: PartialOrd + Copy>(data: Vec<T>) -> Vec<T> {
+    // Base case
+    if data.len() <= 1 {
+        return data;
+    }
+
+    // Get the midpoint
+    let middle = data.len()/2;
+
+    // Split the vector in half recursively until there is only one element
+    let left = merge_sort(data[..middle].to_vec());
+    let right = merge_sort(data[middle..].to_vec());
+
+    // Merge and sort the vector elements
+    merge(left, right)
+}
+
+/// Merges two ascending vectors into a single ascending vector.
+pub fn merge<T: PartialOrd + Copy>(left: Vec<T>, right: Vec<T>) -> Vec<T> {
+    merge_slices(&left, &right)
+}
+
+/// Merges two ascending slices into a new ascending vector, without taking ownership of either.
+/// Useful for sorted runs that live in borrowed or memory-mapped data.
+pub fn merge_slices<T: PartialOrd + Copy>(left: &[T], right: &[T]) -> Vec<T> {
+    // Instantiate sorted vector we will return
+    let mut result = Vec::with_capacity(left.len() + right.len());
+
+    // 2 pointers to compare elements in each vector
+    // i - left vector
+    // j - right vector
+    let (mut i, mut j) = (0, 0);
+    // Loop continues as long as there are elements in both vectors that need to be compared and merged
+    while i < left.len() && j < right.len() {
+        if left[i] <= right[j] {
+            result.push(left[i]);
+            i+=1;
+        } else {
+            result.push(right[j]);
+            j+=1;
+        }
+    }
+
+    // Add elements left over from other vector
+    // We can assume the rest of the array is sorted
+    if i < left.len() {
+        result.extend_from_slice(&left[i..]);
+    }
+    if j < right.len() {
+        result.extend_from_slice(&right[j..]);
+    }
+
+    // return sorted vector
+    result
+}
+
+/// Sorts `data` using its own buffer plus one scratch Vec, and hands back the same allocation
+/// (so `as_ptr()` is unchanged).
+///
+/// This is a bottom-up merge sort (see `merge_sort_in`): runs of width 1, 2, 4, ... are merged back
+/// and forth between `data` and the scratch buffer, with a final copy if the last pass ended up in
+/// the scratch buffer.
+pub fn merge_sort_reuse<T: PartialOrd + Copy>(mut data: Vec<T>) -> Vec<T> {
+    if data.len() <= 1 {
+        return data;
+    }
+
+    let mut scratch = data.clone();
+    merge_sort_in(&mut data, &mut scratch);
+    data
+}
+
+/// Sorts `data` in place (ascending) using the first `data.len()` elements of `scratch` as the
+/// merge buffer, without allocating anything. Whatever `scratch` held before is overwritten.
+///
+/// This is the way to keep sorting off the global allocator, e.g. with a buffer carved out of
+/// an arena or one `Vec` reused across many sorts. Being generic over `std::alloc::Allocator`
+/// instead would need the nightly-only `allocator_api` feature.
+///
+/// Panics if `scratch` is shorter than `data`.
+pub fn merge_sort_in<T: PartialOrd + Copy>(data: &mut [T], scratch: &mut [T]) {
+    let n = data.len();
+    assert!(scratch.len() >= n, "scratch buffer is shorter than the data");
+    let scratch = &mut scratch[..n];
+
+    // Tracks which of the two buffers currently holds the sorted runs
+    let mut runs_in_data = true;
+    let mut width = 1;
+    while width < n {
+        let (src, dst) = if runs_in_data { (&*data, &mut *scratch) } else { (&*scratch, &mut *data) };
+
+        // Merge every pair of neighbouring runs of this width into the other buffer
+        for start in (0..n).step_by(2 * width) {
+            let middle = (start + width).min(n);
+            let end = (start + 2 * width).min(n);
+            merge_into(&src[start..middle], &src[middle..end], &mut dst[start..end]);
+        }
+
+        runs_in_data = !runs_in_data;
+        width *= 2;
+    }
+
+    if !runs_in_data {
+        data.copy_from_slice(scratch);
+    }
+}
+
+// Same merge as `merge`, writing into `out` (which must be exactly as long as both inputs together)
+pub(crate) fn merge_into<T: PartialOrd + Copy>(left: &[T], right: &[T], out: &mut [T]) {
+    let (mut i, mut j) = (0, 0);
+    for slot in out.iter_mut() {
+        // Take from left while right is used up or left's head isn't bigger
+        if j == right.len() || (i < left.len() && left[i] <= right[j]) {
+            *slot = left[i];
+            i+=1;
+        } else {
+            *slot = right[j];
+            j+=1;
+        }
+    }
+}
+
+/// Natural merge sort: merges the ascending runs already present in `data` instead of
+/// splitting it blindly in half.
+///
+/// One pass finds the maximal ascending runs, which are then merged with `merge_k_slices`.
+/// Already sorted input is a single run, so it costs one O(n) scan and no merges at all.
+pub fn natural_merge_sort<T: PartialOrd + Copy>(data: Vec<T>) -> Vec<T> {
+    // A new run starts wherever an element is smaller than the one before it
+    let mut starts = vec![0];
+    starts.extend((1..data.len()).filter(|&i| data[i] < data[i - 1]));
+    if starts.len() == 1 {
+        return data;
+    }
+
+    let ends = starts.iter().skip(1).copied().chain([data.len()]);
+    let runs: Vec<&[T]> = starts.iter().zip(ends).map(|(&start, end)| &data[start..end]).collect();
+
+    merge_k_slices(&runs)
+}
+
+/// Counting sort for integers known to lie in `0..=max_value`, in O(n + max_value) time.
+///
+/// The tradeoff is memory: the count array holds `max_value + 1` entries of `usize` no matter
+/// how few elements there are, so this only pays off when the range is small compared to the input.
+/// Returns `SortError::ValueOutOfRange` (before allocating anything) if any value exceeds `max_value`,
+/// or `SortError::RangeTooLarge` if the count array can't even be sized on this target.
+pub fn counting_sort(data: Vec<u32>, max_value: u32) -> Result<Vec<u32>, SortError> {
+    if let Some((index, &value)) = data.iter().enumerate().find(|(_, &value)| value > max_value) {
+        return Err(SortError::ValueOutOfRange { index, value, max_value });
+    }
+
+    // counts[v] is how many times v appears. On 32-bit targets u32::MAX + 1 counts don't fit a usize
+    let count_len = usize::try_from(max_value).ok().and_then(|max| max.checked_add(1));
+    let Some(count_len) = count_len else {
+        return Err(SortError::RangeTooLarge { max_value });
+    };
+    let mut counts = vec![0usize; count_len];
+    for &value in &data {
+        counts[value as usize] += 1;
+    }
+
+    // Write every value back out as many times as it was counted, reusing the input's buffer
+    let mut sorted = data;
+    sorted.clear();
+    for (value, &count) in counts.iter().enumerate() {
+        sorted.extend(std::iter::repeat_n(value as u32, count));
+    }
+
+    Ok(sorted)
+}
+
+/// Same as `merge_sort`, but produces descending order directly instead of sorting
+/// ascending and reversing afterwards.
+pub fn merge_sort_desc<T: PartialOrd + Copy>(data: Vec<T>) -> Vec<T> {
+    if data.len() <= 1 {
+        return data;
+    }
+
+    let middle = data.len()/2;
+    let left = merge_sort_desc(data[..middle].to_vec());
+    let right = merge_sort_desc(data[middle..].to_vec());
+
+    merge_desc(left, right)
+}
+
+/// Merges two descending vectors into a single descending vector.
+///
+/// Ties take the element from `left` first, so the result matches the order that a stable
+/// descending sort would give (not the order of an ascending sort that is then reversed).
+pub fn merge_desc<T: PartialOrd + Copy>(left: Vec<T>, right: Vec<T>) -> Vec<T> {
+    let mut result = Vec::with_capacity(left.len() + right.len());
+
+    let (mut i, mut j) = (0, 0);
+    // Flipped comparison: the larger head goes first
+    while i < left.len() && j < right.len() {
+        if left[i] >= right[j] {
+            result.push(left[i]);
+            i+=1;
+        } else {
+            result.push(right[j]);
+            j+=1;
+        }
+    }
+
+    // Leftovers are already descending, so they can be appended as-is
+    if i < left.len() {
+        result.extend_from_slice(&left[i..]);
+    }
+    if j < right.len() {
+        result.extend_from_slice(&right[j..]);
+    }
+
+    result
+}
+
+/// Merges any number of borrowed ascending runs into one ascending vector.
+///
+/// Runs are merged in a balanced tree (halves first, then the two results), which takes
+/// O(n log k) for k runs holding n elements. None of the runs are copied before merging.
+pub fn merge_k_slices<T: PartialOrd + Copy>(runs: &[&[T]]) -> Vec<T> {
+    match runs {
+        [] => Vec::new(),
+        [run] => run.to_vec(),
+        [left, right] => merge_slices(left, right),
+        _ => {
+            let (left, right) = runs.split_at(runs.len()/2);
+            merge(merge_k_slices(left), merge_k_slices(right))
+        }
+    }
+}
+
+/// Merges chunks that are supposed to already be sorted, after checking that they really are.
+///
+/// Returns `SortError::UnsortedChunk` with the position of the first chunk that isn't ascending,
+/// instead of silently producing unsorted output.
+pub fn from_sorted_chunks<T: PartialOrd + Copy>(chunks: Vec<Vec<T>>) -> Result<Vec<T>, SortError> {
+    if let Some(index) = chunks.iter().position(|chunk| chunk.windows(2).any(|pair| pair[1] < pair[0])) {
+        return Err(SortError::UnsortedChunk { index });
+    }
+
+    let runs: Vec<&[T]> = chunks.iter().map(Vec::as_slice).collect();
+    Ok(merge_k_slices(&runs))
+}
+
+/// Sorts the contents of a `VecDeque` in place (ascending).
+pub fn merge_sort_deque<T: PartialOrd + Copy>(data: &mut VecDeque<T>) {
+    // A deque that has wrapped around its buffer is stored as two pieces, so line them up first
+    let contiguous = data.make_contiguous();
+    let sorted = merge_sort(contiguous.to_vec());
+    contiguous.copy_from_slice(&sorted);
+}
+
+/// Stable merge sort that moves elements instead of copying them, so it also works for
+/// owned types like `String` that are not `Copy`.
+///
+/// Elements are never cloned, which means a `Vec<Cow<str>>` can be sorted without allocating
+/// for its entries: `Cow::Borrowed` values stay borrowed and `Cow::Owned` values keep their buffers.
+pub fn merge_sort_owned<T: PartialOrd>(data: Vec<T>) -> Vec<T> {
+    merge_sort_owned_by(data, &mut |a: &T, b: &T| a <= b)
+}
+
+/// Stable merge sort ordered by `compare`, the equivalent of `slice::sort_by`.
+/// Like `merge_sort_owned` it moves elements, so `T` doesn't need to be `Copy` (or even `PartialOrd`).
+pub fn merge_sort_by<T, F: FnMut(&T, &T) -> Ordering>(data: Vec<T>, mut compare: F) -> Vec<T> {
+    merge_sort_owned_by(data, &mut |a: &T, b: &T| compare(a, b) != Ordering::Greater)
+}
+
+/// Stable merge sort by the key that `key` extracts from each element, the equivalent of
+/// `slice::sort_by_key`. The key is recomputed for every comparison, so it should be cheap.
+/// Returning `std::cmp::Reverse(key)` sorts by that key descending, e.g.
+/// `merge_sort_by_key(tasks, |task| Reverse(task.priority))` for highest priority first.
+///
+/// ```
+/// use std::cmp::Reverse;
+/// use multithreaded_sorting_rust::merge_sort_by_key;
+///
+/// let tasks = vec![("write", 1), ("review", 3), ("test", 1), ("ship", 2)];
+/// let by_priority = merge_sort_by_key(tasks, |task| Reverse(task.1));
+/// // Highest priority first, and the two priority-1 tasks keep their input order
+/// assert_eq!(by_priority, [("review", 3), ("ship", 2), ("write", 1), ("test", 1)]);
+/// ```
+pub fn merge_sort_by_key<T, K: PartialOrd, F: FnMut(&T) -> K>(data: Vec<T>, mut key: F) -> Vec<T> {
+    merge_sort_owned_by(data, &mut |a: &T, b: &T| key(a) <= key(b))
+}
+
+/// Stable merge sort of strings ignoring case, e.g. for display lists: `["banana", "Apple", "apple"]`
+/// becomes `["Apple", "apple", "banana"]`. The strings come back unchanged, and ones that only
+/// differ in case keep their input order.
+///
+/// Each string's lowercase form (`str::to_lowercase`) is computed once up front and used as its
+/// sort key. For locale-aware ordering, see `merge_sort_collated` (feature "collation").
+pub fn merge_sort_case_insensitive(data: Vec<String>) -> Vec<String> {
+    let keyed: Vec<(String, String)> = data.into_iter().map(|s| (s.to_lowercase(), s)).collect();
+    let sorted = merge_sort_by(keyed, |a, b| a.0.cmp(&b.0));
+    sorted.into_iter().map(|(_, s)| s).collect()
+}
+
+/// Merge sort that also returns how many element comparisons it made.
+///
+/// Handy for checking the O(n log n) bound by hand: sorting n elements never takes more than
+/// about n * log2(n) comparisons, and already sorted input needs only about half of that.
+pub fn merge_sort_instrumented<T: PartialOrd + Copy>(data: Vec<T>) -> (Vec<T>, u64) {
+    let mut comparisons = 0;
+    // Every comparison the merge makes goes through this closure, so counting there counts them all
+    let sorted = merge_sort_owned_by(data, &mut |a: &T, b: &T| {
+        comparisons += 1;
+        a <= b
+    });
+    (sorted, comparisons)
+}
+
+// Shared by the move-based sorts: `in_order(a, b)` says whether `a` may be placed before `b`.
+// Taking the left element whenever it holds is what keeps the merge stable.
+fn merge_sort_owned_by<T, F: FnMut(&T, &T) -> bool>(mut data: Vec<T>, in_order: &mut F) -> Vec<T> {
+    if data.len() <= 1 {
+        return data;
+    }
+
+    // split_off hands the back half over without copying or cloning anything
+    let right = data.split_off(data.len()/2);
+    let left = merge_sort_owned_by(data, in_order);
+    let right = merge_sort_owned_by(right, in_order);
+
+    merge_owned_by(left, right, in_order)
+}
+
+fn merge_owned_by<T, F: FnMut(&T, &T) -> bool>(left: Vec<T>, right: Vec<T>, in_order: &mut F) -> Vec<T> {
+    let mut result = Vec::with_capacity(left.len() + right.len());
+
+    // Peek at both heads and move whichever one comes first into the result
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        let next = if in_order(l, r) { left.next() } else { right.next() };
+        result.extend(next);
+    }
+
+    // At most one of these still has elements
+    result.extend(left);
+    result.extend(right);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::cmp::Reverse;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::test_util::{keyed, random_values, stable_sorted, std_sorted, time_ms, Keyed};
+
+    // Ids of every Tracked value dropped so far. Only one test creates them, so it can't see
+    // drops from other tests running at the same time.
+    static DROPPED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    // A non-Copy value that records its own drop, to catch elements lost or duplicated by a
+    // move-based sort
+    #[derive(Debug, PartialEq, PartialOrd)]
+    struct Tracked(u32);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROPPED.lock().unwrap().push(self.0);
+        }
+    }
+
+    // Sorts 0..len (scrambled) as Tracked values with `sort` and checks every value is dropped
+    // exactly once: none during the sort, all of them once the result goes away
+    fn assert_drops_each_once(len: u32, sort: impl Fn(Vec<Tracked>) -> Vec<Tracked>) {
+        DROPPED.lock().unwrap().clear();
+        let data: Vec<Tracked> = (0..len).map(|i| Tracked((i * 7919) % len)).collect();
+
+        let sorted = sort(data);
+        assert!(DROPPED.lock().unwrap().is_empty(), "values were dropped during the sort");
+        assert!(sorted.iter().map(|tracked| tracked.0).eq(0..len));
+
+        drop(sorted);
+        let mut dropped = std::mem::take(&mut *DROPPED.lock().unwrap());
+        dropped.sort();
+        assert!(dropped.into_iter().eq(0..len), "every value is dropped exactly once");
+    }
+
+    #[test]
+    fn move_based_sorts_drop_every_element_exactly_once() {
+        // 7919 is prime, so (i * 7919) % len visits every value once for these lengths
+        for len in [1, 2, 3, 10, 1000] {
+            assert_drops_each_once(len, merge_sort_owned);
+            assert_drops_each_once(len, |data| merge_sort_by(data, |a: &Tracked, b: &Tracked| a.0.cmp(&b.0)));
+            assert_drops_each_once(len, |data| merge_sort_by_key(data, |tracked| tracked.0));
+        }
+    }
+
+    #[test]
+    fn merge_sort_owned_keeps_cows_borrowed_without_allocating() {
+        let text = String::from("pear fig apple kiwi");
+        let words: Vec<Cow<str>> = vec![
+            Cow::Borrowed(&text[0..4]),
+            Cow::Owned("banana".to_string()),
+            Cow::Borrowed(&text[5..8]),
+            Cow::Borrowed(&text[9..14]),
+            Cow::Owned("cherry".to_string()),
+            Cow::Borrowed(&text[15..19]),
+        ];
+        let pointers: Vec<(String, *const u8)> =
+            words.iter().map(|word| (word.to_string(), word.as_ptr())).collect();
+
+        let sorted = merge_sort_owned(words);
+        assert_eq!(sorted, ["apple", "banana", "cherry", "fig", "kiwi", "pear"]);
+        // Every entry is still the same buffer, so the borrowed ones still point into `text`
+        for word in &sorted {
+            let (_, before) = pointers.iter().find(|(value, _)| value == word).unwrap();
+            assert_eq!(word.as_ptr(), *before, "{word} moved to a new buffer");
+            let borrowed = !["banana", "cherry"].contains(&word.as_ref());
+            assert_eq!(matches!(word, Cow::Borrowed(_)), borrowed, "{word}");
+        }
+    }
+
+    #[test]
+    fn merge_sort_desc_matches_ascending_then_reversed() {
+        for (len, range) in [(0, 1), (1, 1), (2, 5), (1000, 1_000_000), (1000, 4), (5000, 2)] {
+            let data = random_values(len, range, len as u64 + 1);
+            let mut expected = std_sorted(&data);
+            expected.reverse();
+            assert_eq!(merge_sort_desc(data), expected, "len {len}, range {range}");
+        }
+        assert_eq!(merge_desc(vec![9, 4, 4, 1], vec![8, 4, 0]), [9, 8, 4, 4, 4, 1, 0]);
+    }
+
+    #[test]
+    fn merge_sort_desc_keeps_ties_in_input_order() {
+        let values = random_values(2000, 10, 4);
+        let mut pairs: Vec<(i64, usize)> = values.iter().copied().zip(0..).collect();
+        pairs.sort_by_key(|pair| Reverse(pair.0));
+        let expected: Vec<Keyed> = pairs.into_iter().map(|(value, index)| Keyed(value, index)).collect();
+        assert_eq!(merge_sort_desc(keyed(&values)), expected);
+
+        // merge_desc takes from the left on ties
+        let merged = merge_desc(vec![Keyed(2, 0), Keyed(1, 1)], vec![Keyed(2, 2)]);
+        assert_eq!(merged, [Keyed(2, 0), Keyed(2, 2), Keyed(1, 1)]);
+    }
+
+    // Benchmark, so not part of the normal run:
+    // cargo test --release --lib -- --ignored --test-threads=1 --nocapture merge_sort_desc_vs
+    #[test]
+    #[ignore]
+    fn merge_sort_desc_vs_sorting_then_reversing() {
+        let data = random_values(4_000_000, i64::MAX, 5);
+
+        let start = Instant::now();
+        let direct = merge_sort_desc(data.clone());
+        let direct_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        let start = Instant::now();
+        let mut reversed = merge_sort(data);
+        reversed.reverse();
+        let reversed_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        println!("merge_sort_desc: {direct_ms:.1} ms, merge_sort + reverse: {reversed_ms:.1} ms");
+        assert_eq!(direct, reversed);
+    }
+
+    #[test]
+    fn merge_slices_merges_borrowed_runs_of_one_array() {
+        // Three ascending runs side by side in one buffer, merged straight from it
+        let buffer = keyed(&[1, 4, 7, 1, 4, 9, 4]);
+        let (first, rest) = buffer.split_at(3);
+        let (second, third) = rest.split_at(3);
+
+        // Ties take from the left run first
+        let expected = [Keyed(1, 0), Keyed(1, 3), Keyed(4, 1), Keyed(4, 4), Keyed(7, 2), Keyed(9, 5)];
+        assert_eq!(merge_slices(first, second), expected);
+        assert_eq!(merge_slices(first, &[]), first);
+        assert_eq!(merge_slices(&[], second), second);
+
+        let merged = merge_k_slices(&[first, &[], second, third, &[]]);
+        let expected = [(1, 0), (1, 3), (4, 1), (4, 4), (4, 6), (7, 2), (9, 5)];
+        assert_eq!(merged, expected.map(|(value, index)| Keyed(value, index)));
+        assert!(merge_k_slices::<i32>(&[]).is_empty());
+        assert!(merge_k_slices::<i32>(&[&[], &[]]).is_empty());
+        assert_eq!(merge_k_slices(&[second]), second);
+    }
+
+    // Benchmark, so not part of the normal run:
+    // cargo test --release --lib -- --ignored --test-threads=1 --nocapture counting_sort_vs
+    #[test]
+    #[ignore]
+    fn counting_sort_vs_merge_sort_on_a_small_range() {
+        let data: Vec<u32> = random_values(10_000_000, 1000, 9).into_iter().map(|x| x as u32).collect();
+
+        let copy = data.clone();
+        let (counted, counting_ms) = time_ms(|| counting_sort(copy, 999).unwrap());
+        let (merged, merge_ms) = time_ms(|| merge_sort(data));
+        assert_eq!(counted, merged);
+
+        println!("10M values in 0..1000: counting_sort {counting_ms:.1} ms, merge_sort {merge_ms:.1} ms");
+    }
+
+    #[test]
+    fn counting_sort_sorts_values_in_range() {
+        let data: Vec<u32> = random_values(5000, 1000, 3).into_iter().map(|value| value as u32).collect();
+        let mut expected = data.clone();
+        expected.sort();
+        assert_eq!(counting_sort(data, 999), Ok(expected));
+        assert_eq!(counting_sort(vec![], 0), Ok(vec![]));
+        assert_eq!(counting_sort(vec![0, 0, 0], 0), Ok(vec![0, 0, 0]));
+    }
+
+    #[test]
+    fn counting_sort_rejects_values_above_the_maximum() {
+        let error = counting_sort(vec![1, 7, 3, 9], 5);
+        assert_eq!(error, Err(SortError::ValueOutOfRange { index: 1, value: 7, max_value: 5 }));
+    }
+
+    #[test]
+    fn merge_sort_deque_sorts_a_wrapped_deque() {
+        let mut deque = VecDeque::with_capacity(8);
+        deque.extend([90, 80, 70, 60, 50, 40]);
+        deque.drain(..4);
+        // Pushing past the end of the buffer wraps around to the front
+        deque.extend([5, 30, 1, 20, 10]);
+        assert!(!deque.as_slices().1.is_empty(), "the deque should wrap around its buffer");
+
+        merge_sort_deque(&mut deque);
+        assert_eq!(deque, [1, 5, 10, 20, 30, 40, 50]);
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&50));
+    }
+
+    #[test]
+    fn from_sorted_chunks_merges_valid_chunks() {
+        let chunks = vec![vec![1, 4, 9], vec![], vec![2, 3, 10], vec![5]];
+        assert_eq!(from_sorted_chunks(chunks), Ok(vec![1, 2, 3, 4, 5, 9, 10]));
+        assert_eq!(from_sorted_chunks(Vec::<Vec<i32>>::new()), Ok(vec![]));
+    }
+
+    #[test]
+    fn from_sorted_chunks_reports_the_first_unsorted_chunk() {
+        let chunks = vec![vec![1, 2], vec![3, 3, 4], vec![7, 5], vec![9, 8]];
+        assert_eq!(from_sorted_chunks(chunks), Err(SortError::UnsortedChunk { index: 2 }));
+    }
+
+    #[test]
+    fn merge_sort_instrumented_counts_comparisons() {
+        assert_eq!(merge_sort_instrumented(Vec::<i32>::new()), (vec![], 0));
+        assert_eq!(merge_sort_instrumented(vec![7]), (vec![7], 0));
+        assert_eq!(merge_sort_instrumented(vec![2, 1]), (vec![1, 2], 1));
+        // (1 2) and (3 4) take one comparison each, then 1 and 2 are both taken before 3
+        assert_eq!(merge_sort_instrumented(vec![1, 2, 3, 4]), (vec![1, 2, 3, 4], 4));
+        // Interleaved halves (1 3) and (2 4) need every comparison the final merge can make
+        assert_eq!(merge_sort_instrumented(vec![1, 3, 2, 4]), (vec![1, 2, 3, 4], 5));
+    }
+
+    #[test]
+    fn merge_sort_instrumented_stays_within_n_log_n() {
+        for len in [10, 100, 1000, 10_000] {
+            let data = random_values(len, 1_000_000, len as u64);
+            let (sorted, comparisons) = merge_sort_instrumented(data.clone());
+            assert_eq!(sorted, std_sorted(&data));
+
+            // Random input averages about n * log2(n) - 1.25n, never more than n * log2(n)
+            let n = len as f64;
+            let comparisons = comparisons as f64;
+            assert!(comparisons <= n * n.log2(), "len {len}: {comparisons} comparisons");
+            assert!(comparisons >= n * n.log2() - 1.5 * n, "len {len}: {comparisons} comparisons");
+        }
+    }
+
+    #[test]
+    fn merge_sort_case_insensitive_keeps_casing_and_input_order() {
+        let sort = |words: &[&str]| {
+            merge_sort_case_insensitive(words.iter().map(|word| word.to_string()).collect())
+        };
+        assert_eq!(sort(&["banana", "Apple", "apple"]), ["Apple", "apple", "banana"]);
+        // Ties keep their input order whichever casing comes first
+        assert_eq!(sort(&["banana", "apple", "Apple"]), ["apple", "Apple", "banana"]);
+        assert_eq!(sort(&["b", "APPLE", "B", "apple"]), ["APPLE", "apple", "b", "B"]);
+    }
+
+    #[test]
+    fn merge_sort_reuse_returns_the_same_allocation() {
+        for len in [0, 1, 2, 3, 100, 4097] {
+            let data = random_values(len, 1000, len as u64 + 1);
+            let mut expected = data.clone();
+            expected.sort();
+
+            let before = data.as_ptr();
+            let sorted = merge_sort_reuse(data);
+            assert_eq!(sorted.as_ptr(), before, "len {len}");
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn reverse_keys_sort_descending_and_stay_stable() {
+        let values = random_values(500, 10, 4);
+        let pairs: Vec<(i64, usize)> = values.iter().copied().zip(0..).collect();
+
+        let mut expected = pairs.clone();
+        expected.sort_by_key(|pair| Reverse(pair.0));
+        assert_eq!(merge_sort_by_key(pairs.clone(), |pair| Reverse(pair.0)), expected);
+
+        let reversed = merge_sort(values.iter().copied().map(Reverse).collect());
+        let descending: Vec<i64> = reversed.into_iter().map(|value| value.0).collect();
+        let mut expected = values.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(descending, expected);
+    }
+
+    #[test]
+    fn natural_merge_sort_merges_the_existing_runs() {
+        let mut runs: Vec<i64> = (0..300).collect();
+        runs.extend(-50..10);
+        runs.extend([7, 7, 3]);
+        let inputs = [runs, random_values(2000, 100, 8), (0..500).rev().collect(), vec![], vec![4]];
+        for data in inputs {
+            assert_eq!(natural_merge_sort(data.clone()), std_sorted(&data), "len {}", data.len());
+        }
+        assert_eq!(natural_merge_sort(vec![1.5, 2.0, -1.0, 0.0]), [-1.0, 0.0, 1.5, 2.0]);
+    }
+
+    #[test]
+    fn natural_merge_sort_is_stable_across_runs() {
+        let values = random_values(1000, 10, 12);
+        assert_eq!(natural_merge_sort(keyed(&values)), stable_sorted(&values));
+    }
+}
@@ -0,0 +1,123 @@
+// STRUCT-OF-ARRAYS SORTING
+// Columnar data keeps each field in its own Vec, so a row is the same index across all of them.
+// Sorting by one column means computing the sort order once and then moving every column by it.
+
+use crate::sequential::merge_sort_by_key;
+
+/// A column of a struct-of-arrays table that `sort_soa` can reorder in place.
+///
+/// Implemented for `Vec`s and `&mut` slices of any element type. Elements are only swapped,
+/// never copied, so they don't need to be `Copy` or `Clone`.
+pub trait Column {
+    /// Number of rows (elements) in the column.
+    fn rows(&self) -> usize;
+
+    /// Reorders the column so position i ends up holding the element that was at `order[i]`.
+    /// Panics if `order` isn't the same length as the column.
+    fn apply_order(&mut self, order: &[usize]);
+}
+
+impl<T> Column for Vec<T> {
+    fn rows(&self) -> usize {
+        self.len()
+    }
+
+    fn apply_order(&mut self, order: &[usize]) {
+        apply_order(self, order);
+    }
+}
+
+// Slices can't be made into `dyn Column` (they're unsized), but a borrowed slice can
+impl<T> Column for &mut [T] {
+    fn rows(&self) -> usize {
+        self.len()
+    }
+
+    fn apply_order(&mut self, order: &[usize]) {
+        apply_order(self, order);
+    }
+}
+
+/// Returns the stable sort order of `keys`: element i is the index of the key that belongs at
+/// position i once sorted ascending. `keys` itself isn't changed.
+pub fn sort_permutation<K: PartialOrd + Copy>(keys: &[K]) -> Vec<usize> {
+    merge_sort_by_key((0..keys.len()).collect(), |&i| keys[i])
+}
+
+/// Sorts the rows of a struct-of-arrays table by `key_col` (ascending, stable), moving every
+/// column in `other_cols` the same way so the rows stay aligned.
+///
+/// Panics if any of `other_cols` has a different length than `key_col`, before anything is moved.
+pub fn sort_soa<K: PartialOrd + Copy>(key_col: &mut [K], other_cols: &mut [&mut dyn Column]) {
+    assert!(
+        other_cols.iter().all(|column| column.rows() == key_col.len()),
+        "every column must have as many rows as the key column"
+    );
+
+    let order = sort_permutation(key_col);
+    apply_order(key_col, &order);
+    for column in other_cols {
+        column.apply_order(&order);
+    }
+}
+
+// Moves element order[i] of `column` to position i, for every i
+fn apply_order<T>(column: &mut [T], order: &[usize]) {
+    assert_eq!(order.len(), column.len(), "column length doesn't match the sort order");
+
+    // Follow each cycle of the permutation with swaps, so nothing needs a temporary copy
+    let mut placed = vec![false; order.len()];
+    for start in 0..order.len() {
+        let mut position = start;
+        while !placed[position] {
+            placed[position] = true;
+            let source = order[position];
+            if source == start {
+                break;
+            }
+            column.swap(position, source);
+            position = source;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::random_values;
+
+    #[test]
+    fn sort_soa_keeps_the_columns_aligned() {
+        let mut ages = vec![41, 23, 35, 23];
+        let mut names = vec!["ann".to_string(), "bob".to_string(), "cy".to_string(), "di".to_string()];
+        let mut scores = [7.5, 9.0, 6.0, 8.5];
+        sort_soa(&mut ages, &mut [&mut names, &mut scores.as_mut_slice()]);
+
+        // Rows move together, and the two 23s keep their input order
+        assert_eq!(ages, [23, 23, 35, 41]);
+        assert_eq!(names, ["bob", "di", "cy", "ann"]);
+        assert_eq!(scores, [9.0, 8.5, 6.0, 7.5]);
+    }
+
+    #[test]
+    fn sort_soa_matches_sorting_the_rows_together() {
+        for len in [0, 1, 2, 300] {
+            let keys = random_values(len, 13, len as u64 + 1);
+            let mut rows: Vec<(i64, usize)> = keys.iter().copied().zip(0..).collect();
+            rows.sort_by_key(|row| row.0);
+
+            let mut key_col = keys.clone();
+            let mut positions: Vec<usize> = (0..len).collect();
+            assert_eq!(sort_permutation(&keys), rows.iter().map(|row| row.1).collect::<Vec<_>>());
+            sort_soa(&mut key_col, &mut [&mut positions]);
+            assert_eq!(key_col, rows.iter().map(|row| row.0).collect::<Vec<_>>());
+            assert_eq!(positions, rows.iter().map(|row| row.1).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "every column must have as many rows")]
+    fn sort_soa_rejects_columns_of_the_wrong_length() {
+        sort_soa(&mut [2, 1], &mut [&mut vec![1]]);
+    }
+}
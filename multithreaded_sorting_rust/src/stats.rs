@@ -0,0 +1,87 @@
+// SUMMARY FUNCTIONS
+// Helpers for profiling data that are built on top of a single sort.
+
+use crate::sequential::merge_sort;
+
+/// Returns the element at each quantile in `qs`, sorting `data` only once.
+///
+/// Uses the nearest-rank convention: quantile `q` of `n` elements is the element at sorted
+/// position `ceil(q * n)` (1-based), with `q = 0.0` mapping to the minimum. So `0.5` is the
+/// lower median and `1.0` is the maximum. No interpolation is done, every result is an input element.
+///
+/// # Panics
+/// Panics if a quantile is outside `0.0..=1.0` (or NaN), or if `data` is empty while `qs` is not.
+pub fn quantiles<T: PartialOrd + Copy>(data: Vec<T>, qs: &[f64]) -> Vec<T> {
+    // Validate everything up front so a bad quantile doesn't cost a full sort
+    for &q in qs {
+        assert!((0.0..=1.0).contains(&q), "quantile {q} is outside 0.0..=1.0");
+    }
+    if qs.is_empty() {
+        return Vec::new();
+    }
+    assert!(!data.is_empty(), "cannot take quantiles of an empty input");
+
+    let sorted = merge_sort(data);
+    let n = sorted.len();
+
+    qs.iter()
+        .map(|&q| {
+            // q * n can land a few ulps above a whole number (0.07 * 100 is 7.000000000000001),
+            // which would round up a whole rank, so pull it back down by that much first
+            let scaled = q * n as f64;
+            let rank = (scaled - scaled * 4.0 * f64::EPSILON).ceil() as usize;
+            // Nearest rank is 1-based, so shift down by one and keep q = 0.0 at the first element
+            sorted[rank.clamp(1, n) - 1]
+        })
+        .collect()
+}
+
+/// Sorts `data` and also returns its minimum and maximum, which are `None` for an empty input.
+pub fn sort_with_extremes<T: PartialOrd + Copy>(data: Vec<T>) -> (Vec<T>, Option<T>, Option<T>) {
+    let sorted = merge_sort(data);
+    // After sorting the extremes are just the ends, no extra pass needed
+    let (min, max) = (sorted.first().copied(), sorted.last().copied());
+    (sorted, min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_use_the_nearest_rank() {
+        let data: Vec<i32> = (1..=10).rev().collect();
+        assert_eq!(quantiles(data.clone(), &[0.0, 0.1, 0.25, 0.5, 0.9, 1.0]), [1, 1, 3, 5, 9, 10]);
+        assert_eq!(quantiles(vec![2.5, -1.0, 4.0], &[0.5]), [2.5]);
+
+        // None of these multiply out to a whole number exactly in f64
+        assert_eq!(quantiles((1..=100).collect(), &[0.07, 0.14, 0.28, 0.55]), [7, 14, 28, 55]);
+        for n in 1..=200 {
+            let data: Vec<usize> = (1..=n).collect();
+            let qs: Vec<f64> = (0..=n).map(|k| k as f64 / n as f64).collect();
+            let expected: Vec<usize> = (0..=n).map(|k| k.max(1)).collect();
+            assert_eq!(quantiles(data, &qs), expected, "n {n}");
+        }
+        assert_eq!(quantiles(data, &[]), Vec::<i32>::new());
+        assert_eq!(quantiles(Vec::<i32>::new(), &[]), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn sort_with_extremes_returns_the_ends() {
+        assert_eq!(sort_with_extremes(vec![3, -7, 12, 0]), (vec![-7, 0, 3, 12], Some(-7), Some(12)));
+        assert_eq!(sort_with_extremes(vec![4.5]), (vec![4.5], Some(4.5), Some(4.5)));
+        assert_eq!(sort_with_extremes(Vec::<u8>::new()), (vec![], None, None));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside 0.0..=1.0")]
+    fn quantiles_reject_out_of_range_quantiles() {
+        quantiles(vec![1, 2, 3], &[0.5, 1.5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty input")]
+    fn quantiles_reject_empty_input() {
+        quantiles(Vec::<i32>::new(), &[0.5]);
+    }
+}
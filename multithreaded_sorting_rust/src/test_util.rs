@@ -0,0 +1,53 @@
+// TEST HELPERS
+// Shared by the unit tests of every module. No dependencies, so random data comes from a small
+// xorshift generator with a fixed seed, which keeps every run reproducible.
+
+// `len` pseudo-random values in 0..range, the same ones for the same seed
+pub(crate) fn random_values(len: usize, range: i64, seed: u64) -> Vec<i64> {
+    let mut state = seed.max(1);
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % range as u64) as i64
+        })
+        .collect()
+}
+
+// The reference result every sort is checked against
+pub(crate) fn std_sorted<T: Ord + Clone>(data: &[T]) -> Vec<T> {
+    let mut sorted = data.to_vec();
+    sorted.sort();
+    sorted
+}
+
+// Compares by the first field only, so sorting it shows whether equal keys keep their input order
+// (the second field)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Keyed(pub(crate) i64, pub(crate) usize);
+
+impl PartialOrd for Keyed {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+// Pairs every value with its input position
+pub(crate) fn keyed(values: &[i64]) -> Vec<Keyed> {
+    values.iter().enumerate().map(|(index, &value)| Keyed(value, index)).collect()
+}
+
+// What a stable sort of `keyed(values)` must produce
+pub(crate) fn stable_sorted(values: &[i64]) -> Vec<Keyed> {
+    let mut pairs: Vec<(i64, usize)> = values.iter().copied().zip(0..).collect();
+    pairs.sort_by_key(|pair| pair.0);
+    pairs.into_iter().map(|(value, index)| Keyed(value, index)).collect()
+}
+
+// Runs `f` once, returning its result and how long it took in milliseconds, for the benchmarks
+pub(crate) fn time_ms<R>(f: impl FnOnce() -> R) -> (R, f64) {
+    let start = std::time::Instant::now();
+    let result = f();
+    (result, start.elapsed().as_secs_f64() * 1000.0)
+}
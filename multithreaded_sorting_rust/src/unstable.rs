@@ -0,0 +1,131 @@
+// UNSTABLE SORTING
+// Introsort: quicksort that switches to heap sort if partitioning keeps going badly, and to
+// insertion sort for short ranges. It sorts in place without any scratch memory, but equal
+// elements may end up in a different order than they started in.
+
+use crate::adaptive::{insertion_sort, INSERTION_LEN};
+
+/// Unstable in-place sort (ascending), usually faster than `merge_sort` because it needs no
+/// scratch buffers. Worst case is still O(n log n) thanks to the heap sort fallback.
+pub fn quick_sort<T: PartialOrd + Copy>(mut data: Vec<T>) -> Vec<T> {
+    // Roughly 2 * log2(n) levels of bad pivots are allowed before giving up on quicksort
+    let depth_limit = 2 * (usize::BITS - data.len().leading_zeros());
+    quick_sort_slice(&mut data, depth_limit);
+    data
+}
+
+fn quick_sort_slice<T: PartialOrd + Copy>(mut data: &mut [T], mut depth_limit: u32) {
+    loop {
+        if data.len() <= INSERTION_LEN {
+            insertion_sort(data);
+            return;
+        }
+        if depth_limit == 0 {
+            heap_sort(data);
+            return;
+        }
+        depth_limit -= 1;
+
+        let pivot = partition(data);
+        let (left, right) = data.split_at_mut(pivot);
+        let right = &mut right[1..];
+
+        // Recurse into the smaller side and loop on the bigger one, so the stack stays O(log n)
+        if left.len() < right.len() {
+            quick_sort_slice(left, depth_limit);
+            data = right;
+        } else {
+            quick_sort_slice(right, depth_limit);
+            data = left;
+        }
+    }
+}
+
+// Partitions around the median of the first, middle and last elements and returns the pivot's final index
+pub(crate) fn partition<T: PartialOrd + Copy>(data: &mut [T]) -> usize {
+    let (first, middle, last) = (0, data.len()/2, data.len() - 1);
+    if data[middle] < data[first] {
+        data.swap(middle, first);
+    }
+    if data[last] < data[first] {
+        data.swap(last, first);
+    }
+    if data[last] < data[middle] {
+        data.swap(last, middle);
+    }
+
+    // The median is at `middle` now, park it at the end while partitioning
+    data.swap(middle, last);
+    let pivot = data[last];
+    let mut store = 0;
+    for i in 0..last {
+        if data[i] < pivot {
+            data.swap(i, store);
+            store += 1;
+        }
+    }
+    data.swap(store, last);
+    store
+}
+
+pub(crate) fn heap_sort<T: PartialOrd + Copy>(data: &mut [T]) {
+    // Build a max-heap, then repeatedly move the largest element to the end
+    for root in (0..data.len()/2).rev() {
+        sift_down(data, root);
+    }
+    for end in (1..data.len()).rev() {
+        data.swap(0, end);
+        sift_down(&mut data[..end], 0);
+    }
+}
+
+fn sift_down<T: PartialOrd + Copy>(heap: &mut [T], mut root: usize) {
+    loop {
+        let mut largest = root;
+        for child in [2 * root + 1, 2 * root + 2] {
+            if child < heap.len() && heap[largest] < heap[child] {
+                largest = child;
+            }
+        }
+        if largest == root {
+            return;
+        }
+        heap.swap(root, largest);
+        root = largest;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{random_values, std_sorted};
+
+    #[test]
+    fn quick_sort_sorts_every_shape_of_input() {
+        for len in [0, 1, 2, INSERTION_LEN, INSERTION_LEN + 1, 1000, 20_000] {
+            let random = random_values(len, 1_000_000, len as u64 + 1);
+            let few_distinct = random_values(len, 3, len as u64 + 2);
+            let ascending: Vec<i64> = (0..len as i64).collect();
+            let descending: Vec<i64> = ascending.iter().rev().copied().collect();
+            let organ_pipe: Vec<i64> = (0..len as i64).map(|i| i.min(len as i64 - i)).collect();
+            for data in [random, few_distinct, ascending, descending, organ_pipe, vec![7; len]] {
+                assert_eq!(quick_sort(data.clone()), std_sorted(&data), "len {len}");
+            }
+        }
+    }
+
+    #[test]
+    fn quick_sort_sorts_floats() {
+        assert_eq!(quick_sort(vec![0.5, -2.0, 3.25, -2.0, 0.0]), [-2.0, -2.0, 0.0, 0.5, 3.25]);
+    }
+
+    #[test]
+    fn heap_sort_fallback_sorts_on_its_own() {
+        for len in [0, 1, 2, 3, 500] {
+            let mut data = random_values(len, 50, len as u64 + 3);
+            let expected = std_sorted(&data);
+            heap_sort(&mut data);
+            assert_eq!(data, expected, "len {len}");
+        }
+    }
+}
@@ -0,0 +1,30 @@
+// Durations (and tuples containing them) are `PartialOrd + Copy`, so they go through the same
+// generic sorts as the integers and floats the demo uses.
+
+use std::time::Duration;
+
+use multithreaded_sorting_rust::{merge_sort, merge_sort_by_key, parallel_sort_scoped};
+
+#[test]
+fn merge_sort_orders_durations() {
+    let sorted = merge_sort(vec![Duration::from_secs(3), Duration::from_millis(500), Duration::ZERO]);
+    assert_eq!(sorted, [Duration::ZERO, Duration::from_millis(500), Duration::from_secs(3)]);
+}
+
+#[test]
+fn parallel_sort_orders_durations() {
+    let mut timings: Vec<Duration> = (0..1000u64).map(|i| Duration::from_micros((i * 7919) % 1000)).collect();
+    let mut expected = timings.clone();
+    expected.sort();
+    parallel_sort_scoped(&mut timings, std::num::NonZeroUsize::new(4).unwrap());
+    assert_eq!(timings, expected);
+}
+
+#[test]
+fn merge_sort_by_key_orders_records_by_their_duration() {
+    let requests =
+        vec![("slow", Duration::from_secs(2)), ("fast", Duration::from_millis(20)), ("mid", Duration::from_secs(1))];
+    let sorted = merge_sort_by_key(requests, |request| request.1);
+    let names: Vec<&str> = sorted.into_iter().map(|request| request.0).collect();
+    assert_eq!(names, ["fast", "mid", "slow"]);
+}
@@ -0,0 +1,79 @@
+// Allocation benchmark for parallel_sort_scoped's shared scratch buffer. It lives in its own test
+// binary so the counting allocator below only slows down (and only counts) this one test.
+// Not part of the normal run:
+// cargo test --release --test scratch_alloc -- --ignored --nocapture
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use multithreaded_sorting_rust::{parallel_sort_scoped, SortConfig};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: forwarded unchanged, so System gets exactly the caller's guarantees
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: `ptr` came from System.alloc above, with this same layout
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Pseudo-random values from a fixed-seed xorshift, so every run sorts the same input
+fn random_values(len: usize) -> Vec<i64> {
+    let mut state = 11u64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as i64
+        })
+        .collect()
+}
+
+// Runs `sort` on a copy of `data`, returning (allocations made, milliseconds taken)
+fn measure(data: &[i64], sort: impl FnOnce(Vec<i64>) -> Vec<i64>) -> (usize, f64) {
+    let mut expected = data.to_vec();
+    expected.sort();
+    let data = data.to_vec();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let sorted = sort(data);
+    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    assert_eq!(sorted, expected);
+    (allocations, elapsed)
+}
+
+#[test]
+#[ignore]
+fn shared_scratch_allocates_less_than_per_worker_buffers() {
+    let data = random_values(4_000_000);
+    let workers = NonZeroUsize::new(64).unwrap();
+
+    let (shared_allocations, shared_ms) = measure(&data, |mut data| {
+        parallel_sort_scoped(&mut data, workers);
+        data
+    });
+    // Every SortConfig worker sorts its own copy of its chunk, with its own buffers
+    let (per_worker_allocations, per_worker_ms) =
+        measure(&data, |data| SortConfig::new().threads(workers).sort(data));
+
+    println!("shared scratch: {shared_allocations} allocations, {shared_ms:.1} ms");
+    println!("per-worker buffers: {per_worker_allocations} allocations, {per_worker_ms:.1} ms");
+    assert!(shared_allocations < per_worker_allocations);
+}
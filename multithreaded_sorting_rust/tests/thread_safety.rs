@@ -0,0 +1,85 @@
+// Concurrency stress test, built to give ThreadSanitizer as many chances as possible to catch a
+// data race in the parallel sorts. It only asserts that every result is correct, so it also runs
+// (quickly) as part of the normal suite. Under TSan, on nightly:
+//
+// RUSTFLAGS="-Zsanitizer=thread" cargo +nightly test -Zbuild-std --target x86_64-unknown-linux-gnu \
+//     --test thread_safety
+//
+// -Zbuild-std rebuilds std with the sanitizer too, otherwise TSan reports false positives inside it.
+
+use std::num::NonZeroUsize;
+use std::thread;
+
+use multithreaded_sorting_rust::{parallel_sample_sort, parallel_sort_scoped, Balancing, SortConfig};
+
+// Application threads sorting at the same time, each spawning its own workers
+const CALLERS: usize = 16;
+const ROUNDS: usize = 20;
+
+// Pseudo-random values from a fixed-seed xorshift, the same ones for the same seed
+fn random_values(len: usize, range: u64, seed: u64) -> Vec<u64> {
+    let mut state = seed.max(1);
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % range
+        })
+        .collect()
+}
+
+fn sorted(data: &[u64]) -> Vec<u64> {
+    let mut sorted = data.to_vec();
+    sorted.sort();
+    sorted
+}
+
+#[test]
+fn concurrent_parallel_sorts_all_match_the_sequential_result() {
+    // One input read by every caller at once, while each sorts its own copies
+    let shared = random_values(4000, 1000, 7);
+    let expected = sorted(&shared);
+
+    thread::scope(|s| {
+        for caller in 0..CALLERS {
+            let (shared, expected) = (&shared, &expected);
+            s.spawn(move || {
+                for round in 0..ROUNDS {
+                    // Vary the worker count so chunk and merge boundaries keep moving
+                    let workers = NonZeroUsize::new(1 + (caller + round) % 9).unwrap();
+
+                    let mut scoped = shared.clone();
+                    parallel_sort_scoped(&mut scoped, workers);
+                    assert_eq!(&scoped, expected, "parallel_sort_scoped, {workers} workers");
+
+                    let mut sampled = shared.clone();
+                    parallel_sample_sort(&mut sampled, workers);
+                    assert_eq!(&sampled, expected, "parallel_sample_sort, {workers} workers");
+
+                    let balancing = if round % 2 == 0 { Balancing::Even } else { Balancing::Adaptive };
+                    let config = SortConfig::new().threads(workers).balancing(balancing);
+                    assert_eq!(&config.sort(shared.clone()), expected, "SortConfig, {workers} workers");
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn sorts_of_neighbouring_borrowed_slices_overlap_in_time() {
+    // Each caller sorts its own slice of one buffer while the others sort theirs, so the workers
+    // of different sorts write right next to each other
+    let mut buffer = random_values(CALLERS * 1000, 50, 3);
+    let expected: Vec<Vec<u64>> = buffer.chunks(1000).map(sorted).collect();
+
+    thread::scope(|s| {
+        for (caller, slice) in buffer.chunks_mut(1000).enumerate() {
+            s.spawn(move || parallel_sort_scoped(slice, NonZeroUsize::new(2 + caller % 7).unwrap()));
+        }
+    });
+
+    for (slice, expected) in buffer.chunks(1000).zip(&expected) {
+        assert_eq!(slice, expected.as_slice());
+    }
+}